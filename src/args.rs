@@ -0,0 +1,83 @@
+// The argument lists each rule was run with, persisted per project.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+
+use crate::state;
+
+// Number of argument lists remembered per rule.
+const LISTS_PER_RULE: usize = 20;
+
+#[derive(Default)]
+pub struct ArgHistory {
+    // Oldest first, without duplicates.
+    entries: HashMap<String, Vec<Vec<String>>>,
+    path: Option<PathBuf>,
+}
+
+impl ArgHistory {
+    // The file has one line `<rule>\t<arg1>\t<arg2>...` per run, oldest first.
+    pub fn load(justfile: &Path) -> ArgHistory {
+        let path = state::project_file(justfile, "args");
+        let mut history = ArgHistory {
+            entries: HashMap::new(),
+            path: None,
+        };
+        if let Some(path) = &path
+            && let Ok(content) = std::fs::read_to_string(path)
+        {
+            for line in content.lines() {
+                let mut parts = line.split('\t');
+                let Some(rule) = parts.next() else {
+                    continue;
+                };
+                history.push(rule, parts.map(str::to_string).collect());
+            }
+        }
+        history.path = path;
+        history
+    }
+
+    pub fn record(&mut self, rule: &str, args: &[String]) {
+        // Arguments containing tabs or newlines can't be stored, and empty lists aren't worth it.
+        if args.is_empty() || args.iter().any(|a| a.contains(['\t', '\n'])) {
+            return;
+        }
+        self.push(rule, args.to_vec());
+        self.save();
+    }
+
+    fn push(&mut self, rule: &str, args: Vec<String>) {
+        let lists = self.entries.entry(rule.to_string()).or_default();
+        lists.retain(|l| *l != args);
+        lists.push(args);
+        if lists.len() > LISTS_PER_RULE {
+            lists.remove(0);
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut content = String::new();
+        for (rule, lists) in &self.entries {
+            for args in lists {
+                content.push_str(&format!("{rule}\t{}\n", args.join("\t")));
+            }
+        }
+        if let Err(err) = std::fs::write(path, content) {
+            eprintln!("{}", format!("Could not save arguments: {err}").dimmed());
+        }
+    }
+
+    // The argument lists the rule was run with, oldest first.
+    pub fn get(&self, rule: &str) -> &[Vec<String>] {
+        self.entries.get(rule).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn last(&self, rule: &str) -> Option<&[String]> {
+        self.get(rule).last().map(Vec::as_slice)
+    }
+}
@@ -0,0 +1,325 @@
+// Commands of the shell itself, typed with a leading `:`.
+use std::cell::RefCell;
+use std::ops::ControlFlow;
+
+use colored::Colorize;
+use rustyline::completion::Pair;
+use rustyline::history::{DefaultHistory, History};
+
+use crate::justfile::Justfile;
+use crate::search::Search;
+use crate::times::{self, Times};
+use crate::usage::Usage;
+use crate::{MyHinter, show};
+
+// What the commands act on.
+pub struct Context<'a> {
+    pub search: &'a Search,
+    pub justfile: &'a RefCell<Justfile>,
+    pub usage: &'a RefCell<Usage>,
+    pub history: &'a DefaultHistory,
+    pub times: &'a Times,
+    pub show_private: bool,
+}
+
+pub struct Builtin {
+    pub name: &'static str,
+    // The arguments, as shown in `:help`.
+    pub usage: &'static str,
+    pub help: &'static str,
+    // Runs the command with the rest of the line. `Break` quits the shell.
+    pub run: fn(&Context, &str) -> ControlFlow<()>,
+    // Completes the argument being typed.
+    pub complete: fn(&MyHinter, &str) -> Vec<Pair>,
+    // The hint shown while typing the arguments.
+    pub hint: fn(&MyHinter, &str) -> Option<String>,
+}
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "help",
+        usage: "[command]",
+        help: "Show the commands of the shell",
+        run: help,
+        complete: |_, word| complete_names(word),
+        hint: |_, args| Some(find(args.trim())?.help.to_string()),
+    },
+    Builtin {
+        name: "list",
+        usage: "[pattern]",
+        help: "List the recipes, or those matching a pattern",
+        run: list,
+        complete: |hinter, word| hinter.complete_rule(word),
+        hint: |hinter, args| {
+            let justfile = hinter.justfile.borrow();
+            let usage = hinter.usage.borrow();
+            let count = justfile
+                .matches(args.trim(), &usage, hinter.show_private())
+                .len();
+            Some(format!("{count} recipes"))
+        },
+    },
+    Builtin {
+        name: "show",
+        usage: "<pattern>",
+        help: "Show the attributes, dependencies and body of a recipe",
+        run: |ctx, args| {
+            let justfile = ctx.justfile.borrow();
+            show::print(
+                &justfile,
+                &ctx.usage.borrow(),
+                ctx.show_private,
+                args.trim(),
+            );
+            ControlFlow::Continue(())
+        },
+        complete: |hinter, word| hinter.complete_rule(word),
+        hint: |hinter, args| {
+            let justfile = hinter.justfile.borrow();
+            let usage = hinter.usage.borrow();
+            let m = justfile.best_match(Some(args.trim()), &usage, hinter.show_private())?;
+            Some(format!("→ {}", m.rule.signature(&m.path)))
+        },
+    },
+    Builtin {
+        name: "reload",
+        usage: "",
+        help: "Read the justfile again",
+        run: |ctx, _| {
+            match crate::read(ctx.search) {
+                Ok((justfile, source)) => {
+                    crate::print_source(ctx.search, &source);
+                    ctx.justfile.replace(justfile);
+                }
+                Err(err) => eprintln!("{}", format!("Error: {err}").bold().red()),
+            }
+            ControlFlow::Continue(())
+        },
+        complete: |_, _| Vec::new(),
+        hint: |_, _| None,
+    },
+    Builtin {
+        name: "history",
+        usage: "[count]",
+        help: "Show the last lines entered, 20 by default",
+        run: history,
+        complete: |_, _| Vec::new(),
+        hint: |_, _| None,
+    },
+    Builtin {
+        name: "times",
+        usage: "[pattern]",
+        help: "Show how long recent runs took, of all recipes or of one",
+        run: times,
+        complete: |hinter, word| hinter.complete_rule(word),
+        hint: |hinter, args| {
+            if args.trim().is_empty() {
+                return None;
+            }
+            let justfile = hinter.justfile.borrow();
+            let usage = hinter.usage.borrow();
+            let m = justfile.best_match(Some(args.trim()), &usage, hinter.show_private())?;
+            Some(format!("→ {}", m.path))
+        },
+    },
+    Builtin {
+        name: "quit",
+        usage: "",
+        help: "Leave the shell",
+        run: |_, _| ControlFlow::Break(()),
+        complete: |_, _| Vec::new(),
+        hint: |_, _| None,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|b| b.name == name)
+}
+
+// Builtins whose name starts with `prefix`.
+fn matching(prefix: &str) -> impl Iterator<Item = &'static Builtin> {
+    BUILTINS.iter().filter(move |b| b.name.starts_with(prefix))
+}
+
+fn complete_names(word: &str) -> Vec<Pair> {
+    matching(word)
+        .map(|b| Pair {
+            display: b.name.to_string(),
+            replacement: b.name.to_string(),
+        })
+        .collect()
+}
+
+// Run the command on a line without its leading `:`.
+pub fn run(ctx: &Context, line: &str) -> ControlFlow<()> {
+    let (name, args) = line.split_once(' ').unwrap_or((line, ""));
+    let Some(builtin) = find(name) else {
+        eprintln!(
+            "{}",
+            format!("Error: unknown command `:{name}`; see `:help`.")
+                .bold()
+                .red()
+        );
+        return ControlFlow::Continue(());
+    };
+    (builtin.run)(ctx, args)
+}
+
+fn help(_: &Context, args: &str) -> ControlFlow<()> {
+    let builtins: Vec<_> = match args.trim() {
+        "" => BUILTINS.iter().collect(),
+        name => find(name).into_iter().collect(),
+    };
+    if builtins.is_empty() {
+        eprintln!(
+            "{}",
+            format!("Error: no command `:{}`.", args.trim())
+                .bold()
+                .red()
+        );
+    }
+    for b in builtins {
+        let command = format!(":{} {}", b.name, b.usage);
+        println!("{} {}", format!("{command:20}").bold(), b.help);
+    }
+    ControlFlow::Continue(())
+}
+
+// Like `just --list`: the signature of each recipe, followed by its documentation.
+fn list(ctx: &Context, args: &str) -> ControlFlow<()> {
+    let justfile = ctx.justfile.borrow();
+    let pattern = args.trim();
+    let matches: Vec<_> = match pattern {
+        "" => justfile
+            .candidates()
+            .into_iter()
+            .filter(|m| m.alias.is_none() && (ctx.show_private || !m.private))
+            .collect(),
+        _ => justfile.matches(pattern, &ctx.usage.borrow(), ctx.show_private),
+    };
+    let signatures: Vec<_> = matches.iter().map(|m| m.rule.signature(&m.path)).collect();
+    let width = signatures
+        .iter()
+        .map(|s| s.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (m, signature) in matches.iter().zip(&signatures) {
+        match &m.rule.doc {
+            Some(doc) => {
+                let doc = format!("# {}", doc.lines().next().unwrap_or_default());
+                println!("    {signature:width$} {}", doc.dimmed());
+            }
+            None => println!("    {signature}"),
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+fn history(ctx: &Context, args: &str) -> ControlFlow<()> {
+    let count = match args.trim() {
+        "" => 20,
+        count => match count.parse() {
+            Ok(count) => count,
+            Err(_) => {
+                eprintln!(
+                    "{}",
+                    format!("Error: not a number: `{count}`.").bold().red()
+                );
+                return ControlFlow::Continue(());
+            }
+        },
+    };
+    let len = ctx.history.len();
+    for (i, line) in ctx
+        .history
+        .iter()
+        .enumerate()
+        .skip(len.saturating_sub(count))
+    {
+        println!("{} {line}", format!("{:>5}", i + 1).dimmed());
+    }
+    ControlFlow::Continue(())
+}
+
+fn times(ctx: &Context, args: &str) -> ControlFlow<()> {
+    let path = match args.trim() {
+        "" => None,
+        pattern => {
+            let justfile = ctx.justfile.borrow();
+            let usage = ctx.usage.borrow();
+            let Some(m) = justfile.best_match(Some(pattern), &usage, ctx.show_private) else {
+                crate::no_match(&justfile, pattern, ctx.show_private);
+                return ControlFlow::Continue(());
+            };
+            Some(m.path)
+        }
+    };
+    let runs: Vec<_> = ctx
+        .times
+        .runs
+        .iter()
+        .filter(|r| path.as_ref().is_none_or(|p| r.path == *p))
+        .collect();
+    let now = crate::usage::now();
+    for run in runs.iter().skip(runs.len().saturating_sub(20)) {
+        let ago = format!("{:>8}", ago(now.saturating_sub(run.start)));
+        let duration = format!("{:>8}", times::format_duration(run.duration));
+        let status = match run.success() {
+            true => format!("{:9}", "✓").green(),
+            false => format!("{:9}", format!("✗ {}", run.status)).red(),
+        };
+        let command = std::iter::once(&run.path).chain(&run.args);
+        let command: Vec<_> = command.map(String::as_str).collect();
+        println!(
+            "{} {duration}  {status} {}",
+            ago.dimmed(),
+            command.join(" ")
+        );
+    }
+    ControlFlow::Continue(())
+}
+
+// E.g. `5s ago` or `3d ago`.
+fn ago(seconds: u64) -> String {
+    match seconds {
+        s if s < 60 => format!("{s}s ago"),
+        s if s < 60 * 60 => format!("{}m ago", s / 60),
+        s if s < 24 * 60 * 60 => format!("{}h ago", s / 60 / 60),
+        s => format!("{}d ago", s / 24 / 60 / 60),
+    }
+}
+
+impl MyHinter<'_> {
+    // The hint for a line starting with `:`: the matching commands while typing the name,
+    // and the hint of the command after that.
+    pub fn builtin_hint(&self, line: &str) -> Option<String> {
+        match line.split_once(' ') {
+            Some((name, args)) => {
+                let builtin = find(name)?;
+                let hint = (builtin.hint)(self, args)
+                    .unwrap_or_else(|| format!("{} {}", builtin.usage, builtin.help));
+                Some(format!("  {}", hint.dimmed()))
+            }
+            None => {
+                let names: Vec<_> = matching(line).map(|b| b.name).collect();
+                let first = find(names.first()?)?;
+                Some(format!(
+                    "  ({}) {}",
+                    names.join(", "),
+                    first.help.dimmed().italic()
+                ))
+            }
+        }
+    }
+
+    // Completion for a line starting with `:`, with `pos` relative to after the `:`.
+    pub fn builtin_complete(&self, line: &str, pos: usize) -> (usize, Vec<Pair>) {
+        let start = line[..pos].rfind(' ').map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        let candidates = match line[..start].split_once(' ') {
+            None => complete_names(word),
+            Some((name, _)) => find(name).map_or(Vec::new(), |b| (b.complete)(self, word)),
+        };
+        (start, candidates)
+    }
+}
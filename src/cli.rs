@@ -0,0 +1,89 @@
+// Command line arguments, mirroring the corresponding flags of `just`.
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+A minimal shell around just-files
+
+Usage: just-shell [OPTIONS]
+
+Options:
+  -f, --justfile <JUSTFILE>                 Use <JUSTFILE> as justfile
+  -d, --working-directory <WORKING-DIRECTORY>
+                                            Use <WORKING-DIRECTORY> as working directory.
+                                            --justfile must also be set
+      --choose                              Start by picking a recipe from a full-screen list
+      --protect <PATTERN>                   Ask before running recipes matching <PATTERN>,
+                                            e.g. 'db-*', unless typed exactly. May be repeated
+      --on-empty <ACTION>                   What an empty line does: run the `default` recipe,
+                                            rerun the `last` command, or do `nothing`
+                                            [default: default]
+  -h, --help                                Print help
+  -V, --version                             Print version";
+
+// What pressing Enter on an empty line does.
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum OnEmpty {
+    // Run the first recipe, like `just` without arguments.
+    #[default]
+    Default,
+    // Run the last command again.
+    Last,
+    Nothing,
+}
+
+#[derive(Default)]
+pub struct Args {
+    pub justfile: Option<PathBuf>,
+    pub working_directory: Option<PathBuf>,
+    pub choose: bool,
+    pub protect: Vec<String>,
+    pub on_empty: OnEmpty,
+}
+
+pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        // Support both `--flag value` and `--flag=value`.
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline_value
+                .map(str::to_string)
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{flag} requires a value"))
+        };
+        match flag.as_str() {
+            "-f" | "--justfile" => parsed.justfile = Some(value()?.into()),
+            "-d" | "--working-directory" => parsed.working_directory = Some(value()?.into()),
+            "--choose" => parsed.choose = true,
+            "--protect" => parsed.protect.push(value()?),
+            "--on-empty" => {
+                parsed.on_empty = match value()?.as_str() {
+                    "default" => OnEmpty::Default,
+                    "last" => OnEmpty::Last,
+                    "nothing" => OnEmpty::Nothing,
+                    other => {
+                        return Err(format!(
+                            "invalid --on-empty `{other}`; expected default, last or nothing"
+                        ));
+                    }
+                }
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            "-V" | "--version" => {
+                println!("just-shell {}", env!("CARGO_PKG_VERSION"));
+                std::process::exit(0);
+            }
+            _ => return Err(format!("unexpected argument `{arg}`")),
+        }
+    }
+    if parsed.working_directory.is_some() && parsed.justfile.is_none() {
+        return Err("--working-directory requires --justfile".to_string());
+    }
+    Ok(parsed)
+}
@@ -0,0 +1,127 @@
+// Tab completion: rule names in the first position, arguments after that.
+use std::sync::atomic::Ordering;
+
+use rustyline::Context;
+use rustyline::completion::{Completer, Pair};
+
+use crate::{MyHinter, watch};
+
+// Candidates for a pattern, remembered so that repeated tabs cycle through them.
+pub struct Cycle {
+    pattern: String,
+    names: Vec<String>,
+}
+
+impl Completer for MyHinter<'_> {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        watch::apply(&self.reloaded, self.justfile);
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        let before = &line[..start];
+        let usage = self.usage.borrow();
+        let show_private = self.show_private.load(Ordering::Relaxed);
+
+        // While prompting for an argument, complete the values used before.
+        if let Some(arg_prompt) = &self.arg_prompt {
+            let candidates = arg_prompt
+                .candidates(&line[..pos])
+                .into_iter()
+                .map(|v| Pair {
+                    display: v.to_string(),
+                    replacement: v.to_string(),
+                })
+                .collect();
+            return Ok((0, candidates));
+        }
+
+        if pos > 0
+            && let Some(command) = line.strip_prefix(':')
+        {
+            let (start, candidates) = self.builtin_complete(command, pos - 1);
+            return Ok((start + 1, candidates));
+        }
+
+        // `?rule` is completed like the rule.
+        if pos > 0
+            && let Some(command) = line.strip_prefix('?')
+        {
+            let (start, candidates) = self.complete(command, pos - 1, ctx)?;
+            return Ok((start + 1, candidates));
+        }
+
+        let Some(pattern) = before.split_whitespace().next() else {
+            return Ok((start, self.complete_rule(word)));
+        };
+
+        // Complete the argument for the parameter at this position.
+        let justfile = self.justfile.borrow();
+        let Some(m) = justfile.best_match(Some(pattern), &usage, show_private) else {
+            return Ok((start, Vec::new()));
+        };
+        let index = before.split_whitespace().count() - 1;
+        if let Some(param) = m.rule.param_at(index)
+            && let Some(choices) = m.rule.choices(&param.name)
+        {
+            let candidates = choices
+                .into_iter()
+                .filter(|c| c.starts_with(word))
+                .map(|c| Pair {
+                    display: c.clone(),
+                    replacement: c,
+                })
+                .collect();
+            return Ok((start, candidates));
+        }
+        self.files.complete(line, pos, ctx)
+    }
+}
+
+impl MyHinter<'_> {
+    // Rule names matching `word`, best first. When the word is ambiguous, rustyline
+    // lists the candidates on the second tab; after that, each tab replaces the word
+    // with the next candidate.
+    pub fn complete_rule(&self, word: &str) -> Vec<Pair> {
+        let mut cycle = self.cycle.borrow_mut();
+        if let Some(c) = cycle.as_ref() {
+            let next = if word == c.pattern {
+                Some(0)
+            } else {
+                c.names.iter().position(|n| n == word).map(|i| i + 1)
+            };
+            if let Some(next) = next {
+                let name = c.names[next % c.names.len()].clone();
+                return vec![Pair {
+                    display: name.clone(),
+                    replacement: name,
+                }];
+            }
+        }
+
+        let usage = self.usage.borrow();
+        let show_private = self.show_private.load(Ordering::Relaxed);
+        let justfile = self.justfile.borrow();
+        let matches = justfile.matches(word, &usage, show_private);
+        let candidates: Vec<_> = matches
+            .iter()
+            .map(|m| Pair {
+                display: match m.alias {
+                    Some(_) => format!("{} → {}", m.name, m.path),
+                    None => m.name.clone(),
+                },
+                replacement: m.name.clone(),
+            })
+            .collect();
+        *cycle = (candidates.len() > 1).then(|| Cycle {
+            pattern: word.to_string(),
+            names: matches.into_iter().map(|m| m.name).collect(),
+        });
+        candidates
+    }
+}
@@ -0,0 +1,115 @@
+// Asking before running a rule with `[confirm]`, or one matching a `--protect` pattern.
+// The shell asks instead of just, so that the question shows the rule the pattern
+// resolved to. It also asks for the dependencies with `[confirm]`, so that just can be
+// told not to ask at all.
+use colored::Colorize;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+
+use crate::MyHinter;
+use crate::justfile::{Justfile, Match};
+use crate::prompt::ArgPrompt;
+
+// The questions to ask before running the rule: one for the rule itself, and one for
+// each dependency with `[confirm]`. Protected rules run without asking when their name
+// was typed exactly.
+pub fn questions(
+    justfile: &Justfile,
+    m: &Match,
+    pattern: Option<&str>,
+    args: &[String],
+    protect: &[String],
+) -> Vec<String> {
+    let mut questions = Vec::new();
+    let command = std::iter::once(&m.path).chain(args);
+    let command: Vec<_> = command.map(String::as_str).collect();
+    let exact = pattern.is_some_and(|p| m.exact(p) || p == m.path);
+    if m.rule.confirm() {
+        questions.push(question(&command.join(" "), m.rule.confirm_message()));
+    } else if !exact && protect.iter().any(|p| glob(p, &m.path)) {
+        let command = command.join(" ").bold();
+        questions.push(format!("{command} is protected. Run it?"));
+    }
+    let module = m.path.rsplit_once("::").map(|(module, _)| module);
+    let dependencies = justfile.module_of(&m.path).confirming_dependencies(m.rule);
+    for dependency in dependencies {
+        let path = match module {
+            Some(module) => format!("{module}::{}", dependency.name),
+            None => dependency.name.clone(),
+        };
+        questions.push(question(&path, dependency.confirm_message()));
+    }
+    questions
+}
+
+// What just would ask for a rule with `[confirm]`.
+fn question(command: &str, message: Option<&str>) -> String {
+    match message {
+        Some(message) => format!("{}: {message}", command.bold()),
+        None => format!("Run {}?", command.bold()),
+    }
+}
+
+// Ask a yes/no question; anything but `y` or `yes` is no.
+pub fn ask(rl: &mut rustyline::Editor<MyHinter, DefaultHistory>, question: &str) -> bool {
+    // Nothing to hint or complete.
+    rl.helper_mut()
+        .unwrap()
+        .set_arg_prompt(Some(ArgPrompt { values: Vec::new() }));
+    let answer = rl.readline(&format!("{question} {} ", "[y/N]".dimmed()));
+    rl.helper_mut().unwrap().set_arg_prompt(None);
+    match answer {
+        Ok(answer) => matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+        Err(ReadlineError::Interrupted | ReadlineError::Eof) => false,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            false
+        }
+    }
+}
+
+// Whether `name` matches `pattern`, in which `*` matches any sequence of characters.
+fn glob(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=name.len())
+                .filter(|&i| name.is_char_boundary(i))
+                .any(|i| glob(rest, &name[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs() {
+        let cases = [
+            ("db-*", "db-reset", true),
+            ("db-*", "db-", true),
+            ("db-*", "db", false),
+            ("db-*", "xdb-reset", false),
+            ("*", "", true),
+            ("*", "anything", true),
+            ("deploy", "deploy", true),
+            ("deploy", "deploy-prod", false),
+            ("*-*", "db-reset", true),
+            ("*-*", "dbreset", false),
+            ("*prod*", "deploy-prod-eu", true),
+            ("*prod*", "deploy-staging", false),
+            ("*-eu", "deploy-prod-eu", true),
+            ("déploi-*", "déploi-é", true),
+            ("*é*", "café-prod", true),
+            ("*é", "café", true),
+            ("*é", "cafe", false),
+        ];
+        for (pattern, name, expected) in cases {
+            assert_eq!(glob(pattern, name), expected, "{pattern} {name}");
+        }
+    }
+}
@@ -0,0 +1,277 @@
+// Build a Justfile from just's own AST, as printed by `just --dump --dump-format json`.
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+use crate::justfile::*;
+
+pub fn load(justfile_path: &Path) -> Result<Justfile, String> {
+    let output = Command::new("just")
+        .arg("--justfile")
+        .arg(justfile_path)
+        .args(["--dump", "--dump-format", "json"])
+        .output()
+        .map_err(|err| format!("could not run just: {err}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr.lines().next().unwrap_or("unknown error").to_string());
+    }
+    let json: Value =
+        serde_json::from_slice(&output.stdout).map_err(|err| format!("invalid json: {err}"))?;
+    Ok(justfile(&json))
+}
+
+pub fn justfile(json: &Value) -> Justfile {
+    let mut rules: Vec<Rule> = object(json, "recipes")
+        .map(|(name, recipe)| rule(name, recipe))
+        .collect();
+    // Recipes are sorted by name in the dump; keep the default recipe first.
+    if let Some(first) = field(json, "first").as_str()
+        && let Some(i) = rules.iter().position(|r| r.name == first)
+    {
+        let rule = rules.remove(i);
+        rules.insert(0, rule);
+    }
+
+    let aliases = object(json, "aliases")
+        .map(|(name, alias)| Alias {
+            alias: name.clone(),
+            rule: string(alias, "target"),
+            attributes: attributes(alias),
+        })
+        .collect();
+
+    let assignments = object(json, "assignments")
+        .map(|(name, assignment)| Assignment {
+            name: name.clone(),
+            value: expression(field(assignment, "value")),
+            export: field(assignment, "export").as_bool().unwrap_or(false),
+        })
+        .collect();
+
+    let settings = object(json, "settings")
+        .filter_map(|(name, value)| {
+            let value = match value {
+                Value::Null | Value::Bool(false) => return None,
+                Value::Bool(true) => None,
+                // The shell settings are dumped as {"command": .., "arguments": [..]}.
+                Value::Object(_) => {
+                    let mut items = vec![expression(field(value, "command"))];
+                    items.extend(array(value, "arguments").iter().map(expression));
+                    Some(format!("[{}]", items.join(", ")))
+                }
+                value => Some(expression(value)),
+            };
+            Some(Setting {
+                name: name.replace('_', "-"),
+                value,
+            })
+        })
+        .collect();
+
+    let modules = object(json, "modules")
+        .map(|(name, module)| Module {
+            name: name.clone(),
+            path: None,
+            optional: false,
+            attributes: Vec::new(),
+            justfile: justfile(module),
+        })
+        .collect();
+
+    Justfile {
+        rules,
+        aliases,
+        assignments,
+        settings,
+        // Imports are already resolved by just.
+        imports: Vec::new(),
+        modules,
+    }
+}
+
+static NULL: Value = Value::Null;
+
+fn field<'j>(json: &'j Value, key: &str) -> &'j Value {
+    json.get(key).unwrap_or(&NULL)
+}
+
+fn array<'j>(json: &'j Value, key: &str) -> &'j [Value] {
+    field(json, key).as_array().map_or(&[], Vec::as_slice)
+}
+
+fn object<'j>(json: &'j Value, key: &str) -> impl Iterator<Item = (&'j String, &'j Value)> {
+    field(json, key).as_object().into_iter().flatten()
+}
+
+fn string(json: &Value, key: &str) -> String {
+    field(json, key).as_str().unwrap_or_default().to_string()
+}
+
+fn rule(name: &str, recipe: &Value) -> Rule {
+    let params = array(recipe, "parameters")
+        .iter()
+        .map(|param| Parameter {
+            name: string(param, "name"),
+            default: match field(param, "default") {
+                Value::Null => None,
+                default => Some(expression(default)),
+            },
+            variadic: match field(param, "kind").as_str() {
+                Some("plus") => Some(Variadic::Plus),
+                Some("star") => Some(Variadic::Star),
+                _ => None,
+            },
+            export: field(param, "export").as_bool().unwrap_or(false),
+        })
+        .collect();
+
+    // Dependencies after the first `priors` run after the recipe.
+    let priors = field(recipe, "priors").as_f64().unwrap_or(f64::INFINITY);
+    let dependencies = array(recipe, "dependencies")
+        .iter()
+        .enumerate()
+        .map(|(i, dep)| Dependency {
+            name: string(dep, "recipe"),
+            args: array(dep, "arguments").iter().map(expression).collect(),
+            after: i as f64 >= priors,
+        })
+        .collect();
+
+    // Each body line is a list of text fragments and `[expression]` interpolations.
+    let body = array(recipe, "body")
+        .iter()
+        .map(|line| {
+            line.as_array()
+                .into_iter()
+                .flatten()
+                .map(|fragment| match fragment {
+                    Value::String(text) => text.clone(),
+                    Value::Array(items) if items.len() == 1 => {
+                        format!("{{{{{}}}}}", expression(&items[0]))
+                    }
+                    fragment => format!("{{{{{}}}}}", expression(fragment)),
+                })
+                .collect()
+        })
+        .collect();
+
+    Rule {
+        name: name.to_string(),
+        doc: field(recipe, "doc").as_str().map(str::to_string),
+        params,
+        dependencies,
+        attributes: attributes(recipe),
+        body,
+        quiet: field(recipe, "quiet").as_bool().unwrap_or(false),
+        source: None,
+        line: None,
+    }
+}
+
+// Attributes are dumped either as a bare name, or as an object {name: argument(s)}.
+fn attributes(json: &Value) -> Vec<Attribute> {
+    let mut attributes = Vec::new();
+    for attribute in array(json, "attributes") {
+        match attribute {
+            Value::String(name) => attributes.push(Attribute {
+                name: name.clone(),
+                args: Vec::new(),
+                keywords: Vec::new(),
+            }),
+            Value::Object(fields) => {
+                for (name, value) in fields {
+                    let mut attribute = Attribute {
+                        name: name.clone(),
+                        args: Vec::new(),
+                        keywords: Vec::new(),
+                    };
+                    match value {
+                        Value::Null => {}
+                        Value::String(arg) => attribute.args.push(arg.clone()),
+                        Value::Array(args) => {
+                            attribute.args.extend(args.iter().map(|arg| match arg {
+                                Value::String(arg) => arg.clone(),
+                                arg => expression(arg),
+                            }))
+                        }
+                        Value::Object(keywords) => attribute.keywords.extend(
+                            keywords.iter().filter_map(|(k, v)| match v {
+                                Value::Null => Some((k.clone(), None)),
+                                v => Some((k.clone(), Some(v.as_str()?.to_string()))),
+                            }),
+                        ),
+                        value => attribute.args.push(expression(value)),
+                    }
+                    attributes.push(attribute);
+                }
+            }
+            _ => {}
+        }
+    }
+    attributes
+}
+
+// Render a dumped expression back into justfile syntax.
+fn expression(json: &Value) -> String {
+    let items = match json {
+        Value::String(s) => return quote(s),
+        Value::Array(items) if !items.is_empty() => items,
+        Value::Null => return String::new(),
+        Value::Bool(b) => return b.to_string(),
+        Value::Number(n) => return n.to_string(),
+        json => return json.to_string(),
+    };
+    let e = expression;
+    match (items[0].as_str().unwrap_or_default(), &items[1..]) {
+        ("variable", [name]) => name.as_str().unwrap_or_default().to_string(),
+        ("evaluate", [command]) => format!("`{}`", command.as_str().unwrap_or_default()),
+        ("call", [name, args @ ..]) => format!(
+            "{}({})",
+            name.as_str().unwrap_or_default(),
+            args.iter().map(e).collect::<Vec<_>>().join(", ")
+        ),
+        ("concatenate", [lhs, rhs]) => format!("{} + {}", e(lhs), e(rhs)),
+        ("join", [Value::Null, rhs]) => format!("/ {}", e(rhs)),
+        ("join", [lhs, rhs]) => format!("{} / {}", e(lhs), e(rhs)),
+        ("and", [lhs, rhs]) => format!("{} && {}", e(lhs), e(rhs)),
+        ("or", [lhs, rhs]) => format!("{} || {}", e(lhs), e(rhs)),
+        ("assert", [condition, error]) => format!("assert({}, {})", e(condition), e(error)),
+        (op @ ("==" | "!=" | "=~"), [lhs, rhs]) => format!("{} {op} {}", e(lhs), e(rhs)),
+        ("if", [condition, then, otherwise]) => {
+            format!(
+                "if {} {{ {} }} else {{ {} }}",
+                e(condition),
+                e(then),
+                e(otherwise)
+            )
+        }
+        // Older versions of just dump the condition inline.
+        ("if", [op, lhs, rhs, then, otherwise]) => format!(
+            "if {} {} {} {{ {} }} else {{ {} }}",
+            e(lhs),
+            op.as_str().unwrap_or_default(),
+            e(rhs),
+            e(then),
+            e(otherwise)
+        ),
+        _ => items.iter().map(e).collect::<Vec<_>>().join(" "),
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
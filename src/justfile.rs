@@ -0,0 +1,488 @@
+use std::path::PathBuf;
+
+use fuzzy_matcher::FuzzyMatcher;
+
+use crate::usage::Usage;
+
+type Matcher = fuzzy_matcher::skim::SkimMatcherV2;
+
+// Rule has one of the forms:
+// - <rule>: [deps]
+// - <rule> <param1> ...: [deps]
+// followed by an indented body.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    // From a comment directly above the rule, or a `[doc]` attribute.
+    pub doc: Option<String>,
+    pub params: Vec<Parameter>,
+    pub dependencies: Vec<Dependency>,
+    pub attributes: Vec<Attribute>,
+    pub body: Vec<String>,
+    // Recipes starting with `@` do not echo their lines.
+    pub quiet: bool,
+    // The file and line the rule is defined at.
+    // `just --dump` does not include them; they are then taken from the parser, if it can.
+    pub source: Option<PathBuf>,
+    pub line: Option<usize>,
+}
+
+// Parameter has the form:
+// [+|*][$]<name>[=<default>]
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    pub name: String,
+    // The default value, as an unevaluated expression.
+    pub default: Option<String>,
+    pub variadic: Option<Variadic>,
+    // `$` parameters are exported as environment variables.
+    pub export: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variadic {
+    // `+`: one or more arguments.
+    Plus,
+    // `*`: zero or more arguments.
+    Star,
+}
+
+impl Parameter {
+    pub fn required(&self) -> bool {
+        self.default.is_none() && self.variadic != Some(Variadic::Star)
+    }
+}
+
+impl Parameter {
+    // How the parameter is shown in a usage hint: `<name>` when required,
+    // `[name=default]` when optional, and `...` for variadic parameters.
+    pub fn usage(&self) -> String {
+        let dots = if self.variadic.is_some() { "..." } else { "" };
+        match &self.default {
+            Some(default) => format!("[{}={default}{dots}]", self.name),
+            None if self.required() => format!("<{}{dots}>", self.name),
+            None => format!("[{}{dots}]", self.name),
+        }
+    }
+
+    // The default, when it is a plain string literal that can be pre-filled.
+    pub fn default_value(&self) -> Option<String> {
+        let default = self.default.as_deref()?;
+        let quote = default.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = default.strip_prefix(quote)?.strip_suffix(quote)?;
+        (!value.contains(quote) && !value.contains('\\')).then(|| value.to_string())
+    }
+}
+
+impl std::fmt::Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.variadic {
+            Some(Variadic::Plus) => write!(f, "+")?,
+            Some(Variadic::Star) => write!(f, "*")?,
+            None => {}
+        }
+        if self.export {
+            write!(f, "$")?;
+        }
+        write!(f, "{}", self.name)?;
+        if let Some(default) = &self.default {
+            write!(f, "={default}")?;
+        }
+        Ok(())
+    }
+}
+
+fn has_attribute(attributes: &[Attribute], name: &str) -> bool {
+    attributes.iter().any(|a| a.name == name)
+}
+
+impl Rule {
+    // Private rules are hidden, like in `just --list`.
+    pub fn private(&self) -> bool {
+        self.name.starts_with('_') || has_attribute(&self.attributes, "private")
+    }
+
+    // Rules with `[confirm]` or `[confirm("<message>")]` ask before running.
+    pub fn confirm(&self) -> bool {
+        has_attribute(&self.attributes, "confirm")
+    }
+
+    pub fn confirm_message(&self) -> Option<&str> {
+        let attribute = self.attributes.iter().find(|a| a.name == "confirm")?;
+        attribute.args.first().map(String::as_str)
+    }
+
+    // The minimum and maximum number of arguments the rule accepts.
+    // Arguments are positional, so everything up to the last required parameter must be given.
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        let min = self
+            .params
+            .iter()
+            .rposition(|p| p.required())
+            .map_or(0, |i| i + 1);
+        let max = match self.params.last() {
+            Some(p) if p.variadic.is_some() => None,
+            _ => Some(self.params.len()),
+        };
+        (min, max)
+    }
+
+    // The parameter receiving the argument at `index`; variadic parameters take all the rest.
+    pub fn param_at(&self, index: usize) -> Option<&Parameter> {
+        match self.params.last() {
+            Some(last) if last.variadic.is_some() && index >= self.params.len() => Some(last),
+            _ => self.params.get(index),
+        }
+    }
+
+    // The allowed values of a parameter, when it has an `[arg("<param>", pattern="a|b|c")]`
+    // attribute whose pattern is a plain list of alternatives.
+    pub fn choices(&self, param: &str) -> Option<Vec<String>> {
+        let attribute = self
+            .attributes
+            .iter()
+            .find(|a| a.name == "arg" && a.args.first().is_some_and(|p| p == param))?;
+        let (_, pattern) = attribute.keywords.iter().find(|(k, _)| k == "pattern")?;
+        let pattern = pattern.as_deref()?;
+        let pattern = pattern.trim_start_matches('^').trim_end_matches('$');
+        let pattern = pattern
+            .strip_prefix('(')
+            .and_then(|p| p.strip_suffix(')'))
+            .unwrap_or(pattern);
+        let literal = |c: char| c.is_alphanumeric() || "-_./:=@,".contains(c);
+        if pattern.is_empty() || !pattern.chars().all(|c| c == '|' || literal(c)) {
+            return None;
+        }
+        Some(pattern.split('|').map(str::to_string).collect())
+    }
+
+    // The rule as invoked through `path`, followed by its parameters.
+    pub fn signature(&self, path: &str) -> String {
+        let mut s = path.to_string();
+        for p in &self.params {
+            s.push(' ');
+            s.push_str(&p.to_string());
+        }
+        s
+    }
+}
+
+// A dependency `<rule>` or `(<rule> <arg1> ...)`.
+// Dependencies after `&&` run after the rule itself.
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    pub name: String,
+    pub args: Vec<String>,
+    pub after: bool,
+}
+
+impl std::fmt::Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.args.is_empty() {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "({} {})", self.name, self.args.join(" "))
+        }
+    }
+}
+
+// Attribute has one of the forms:
+// - [<name>]
+// - [<name>(<arg1>, <key>=<value>, ...)]
+// - [<name>: <arg>]
+#[derive(Debug, Clone)]
+pub struct Attribute {
+    pub name: String,
+    pub args: Vec<String>,
+    // Keyword arguments; bare flags like `long` in `[arg("x", long)]` have no value.
+    pub keywords: Vec<(String, Option<String>)>,
+}
+
+impl std::fmt::Display for Attribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}", self.name)?;
+        let args = self.args.iter().map(|a| format!("{a:?}"));
+        let keywords = self.keywords.iter().map(|(k, v)| match v {
+            Some(v) => format!("{k}={v:?}"),
+            None => k.clone(),
+        });
+        let args: Vec<_> = args.chain(keywords).collect();
+        if !args.is_empty() {
+            write!(f, "({})", args.join(", "))?;
+        }
+        write!(f, "]")
+    }
+}
+
+// Alias has the form:
+// alias <alias> := <rule>
+#[derive(Debug, Clone)]
+pub struct Alias {
+    pub alias: String,
+    pub rule: String,
+    pub attributes: Vec<Attribute>,
+}
+
+impl Alias {
+    pub fn private(&self) -> bool {
+        self.alias.starts_with('_') || has_attribute(&self.attributes, "private")
+    }
+}
+
+// Assignment has the form:
+// [export] <name> := <expression>
+#[derive(Debug, Clone)]
+pub struct Assignment {
+    pub name: String,
+    pub value: String,
+    pub export: bool,
+}
+
+// Setting has one of the forms:
+// - set <name>
+// - set <name> := <expression>
+#[derive(Debug, Clone)]
+pub struct Setting {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+// Import has the form:
+// import[?] '<path>'
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub path: String,
+    pub optional: bool,
+}
+
+// Module has the form:
+// mod[?] <name> ['<path>']
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub name: String,
+    pub path: Option<String>,
+    pub optional: bool,
+    pub attributes: Vec<Attribute>,
+    // The contents of the module, once loaded.
+    pub justfile: Justfile,
+}
+
+impl Module {
+    pub fn private(&self) -> bool {
+        has_attribute(&self.attributes, "private")
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Justfile {
+    pub rules: Vec<Rule>,
+    pub aliases: Vec<Alias>,
+    pub assignments: Vec<Assignment>,
+    pub settings: Vec<Setting>,
+    pub imports: Vec<Import>,
+    pub modules: Vec<Module>,
+}
+
+// A rule matching a pattern, either by its own name or through an alias.
+pub struct Match<'j> {
+    pub rule: &'j Rule,
+    // The full `module::rule` path of the rule.
+    pub path: String,
+    pub alias: Option<&'j Alias>,
+    // The matched name: `path`, or the path of the alias.
+    pub name: String,
+    // Whether the rule, alias or one of the enclosing modules is private.
+    pub private: bool,
+    pub score: i64,
+    // Positions of the matched characters in `name`.
+    pub indices: Vec<usize>,
+}
+
+impl Match<'_> {
+    pub fn exact(&self, pattern: &str) -> bool {
+        self.name == pattern
+    }
+}
+
+thread_local! {
+    static MATCHER: Matcher = Matcher::default();
+}
+
+impl Justfile {
+    pub fn rule(&self, name: &str) -> Option<&Rule> {
+        self.rules.iter().find(|r| r.name == name)
+    }
+
+    // The justfile of the module the rule with the given `module::rule` path is in.
+    pub fn module_of(&self, path: &str) -> &Justfile {
+        let mut justfile = self;
+        let mut modules: Vec<_> = path.split("::").collect();
+        modules.pop();
+        for name in modules {
+            match justfile.modules.iter().find(|m| m.name == name) {
+                Some(module) => justfile = &module.justfile,
+                None => break,
+            }
+        }
+        justfile
+    }
+
+    // The dependencies of the rule, direct or not, that ask for confirmation.
+    pub fn confirming_dependencies<'j>(&'j self, rule: &'j Rule) -> Vec<&'j Rule> {
+        let mut seen = std::collections::HashSet::new();
+        let mut rules = vec![rule];
+        let mut confirming = Vec::new();
+        while let Some(rule) = rules.pop() {
+            for dependency in &rule.dependencies {
+                if seen.insert(&dependency.name)
+                    && let Some(dependency) = self.rule(&dependency.name)
+                {
+                    if dependency.confirm() {
+                        confirming.push(dependency);
+                    }
+                    rules.push(dependency);
+                }
+            }
+        }
+        confirming
+    }
+
+    // All rules and aliases of this justfile and its submodules, unscored.
+    pub fn candidates(&self) -> Vec<Match<'_>> {
+        let mut candidates = Vec::new();
+        self.collect_candidates("", false, &mut candidates);
+        candidates
+    }
+
+    fn collect_candidates<'j>(
+        &'j self,
+        prefix: &str,
+        private: bool,
+        candidates: &mut Vec<Match<'j>>,
+    ) {
+        let candidate = |rule: &'j Rule, alias: Option<&'j Alias>| {
+            let path = format!("{prefix}{}", rule.name);
+            Match {
+                rule,
+                name: alias.map_or(path.clone(), |a| format!("{prefix}{}", a.alias)),
+                path,
+                alias,
+                private: private || rule.private() || alias.is_some_and(|a| a.private()),
+                score: 0,
+                indices: Vec::new(),
+            }
+        };
+        for rule in &self.rules {
+            candidates.push(candidate(rule, None));
+        }
+        for alias in &self.aliases {
+            if let Some(rule) = self.rule(&alias.rule) {
+                candidates.push(candidate(rule, Some(alias)));
+            }
+        }
+        for module in &self.modules {
+            let prefix = format!("{prefix}{}::", module.name);
+            module
+                .justfile
+                .collect_candidates(&prefix, private || module.private(), candidates);
+        }
+    }
+
+    // Rules and aliases matching the pattern, best first.
+    // Exact hits come first, then the match score plus a bonus for frequently used rules.
+    // Each rule is listed only once. Private rules are only included when `show_private`
+    // is set, or when their name is typed exactly.
+    pub fn matches(&self, pattern: &str, usage: &Usage, show_private: bool) -> Vec<Match<'_>> {
+        MATCHER.with(|m| {
+            let mut matches: Vec<_> = self
+                .candidates()
+                .into_iter()
+                .filter(|c| show_private || !c.private || c.exact(pattern))
+                .filter_map(|mut candidate| {
+                    (candidate.score, candidate.indices) =
+                        m.fuzzy_indices(&candidate.name, pattern)?;
+                    Some(candidate)
+                })
+                .collect();
+            matches.sort_by_key(|m| (!m.exact(pattern), -m.score - usage.bonus(&m.path)));
+            let mut seen = std::collections::HashSet::new();
+            matches.retain(|m| seen.insert(m.path.clone()));
+            matches
+        })
+    }
+
+    // Names of rules and aliases close to a pattern that matches nothing, closest first.
+    pub fn suggestions(&self, pattern: &str, show_private: bool) -> Vec<String> {
+        let max_distance = (pattern.chars().count() / 3).max(2);
+        let mut suggestions: Vec<_> = self
+            .candidates()
+            .into_iter()
+            .filter(|c| show_private || !c.private)
+            .map(|c| (edit_distance(pattern, &c.name), c.name))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        suggestions.sort();
+        suggestions.truncate(3);
+        suggestions.into_iter().map(|(_, name)| name).collect()
+    }
+
+    // The rule to run for a pattern. An empty pattern runs the first (default) rule.
+    pub fn best_match(
+        &self,
+        pattern: Option<&str>,
+        usage: &Usage,
+        show_private: bool,
+    ) -> Option<Match<'_>> {
+        let pattern = pattern.unwrap_or("");
+        if pattern.is_empty() {
+            return self.candidates().into_iter().next();
+        }
+        self.matches(pattern, usage, show_private)
+            .into_iter()
+            .next()
+    }
+}
+
+// The number of characters to insert, delete or replace to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let replace = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = replace.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distances() {
+        let cases = [
+            ("", "", 0),
+            ("", "build", 5),
+            ("build", "", 5),
+            ("build", "build", 0),
+            // A transposition is two replacements.
+            ("build", "biuld", 2),
+            ("test", "tset", 2),
+            ("ab", "ba", 2),
+            ("build", "buidl", 2),
+            ("test", "tests", 1),
+            ("deploy", "deplyo", 2),
+            ("lint", "link", 1),
+            ("café", "cafe", 1),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(edit_distance(a, b), expected, "{a} {b}");
+            assert_eq!(edit_distance(b, a), expected, "{b} {a}");
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kind {
+    Name(String),
+    // A string literal, with escapes and quotes already processed.
+    Str(String),
+    // A backtick, with its (unevaluated) command.
+    Backtick(String),
+    // An indented line, i.e. a line of a recipe body.
+    Text(String),
+    Comment(String),
+    ColonEq,
+    Colon,
+    Comma,
+    Eq,
+    EqEq,
+    BangEq,
+    BangTilde,
+    EqTilde,
+    Plus,
+    Star,
+    Slash,
+    Dollar,
+    At,
+    Question,
+    AmpAmp,
+    BarBar,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Newline,
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub kind: Kind,
+    // Byte range in the source.
+    pub span: Range<usize>,
+    // 1-based line number of the start of the token.
+    pub line: usize,
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+struct Lexer<'s> {
+    src: &'s str,
+    pos: usize,
+    line: usize,
+    // Nesting depth of (), [] and {}. Newlines are insignificant inside delimiters.
+    depth: usize,
+    tokens: Vec<Token>,
+}
+
+pub fn lex(src: &str) -> Result<Vec<Token>, ParseError> {
+    let mut lexer = Lexer {
+        src,
+        pos: 0,
+        line: 1,
+        depth: 0,
+        tokens: Vec::new(),
+    };
+    lexer.lex()?;
+    Ok(lexer.tokens)
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_name_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+impl<'s> Lexer<'s> {
+    fn rest(&self) -> &'s str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    fn advance(&mut self, n: usize) {
+        self.line += self.src[self.pos..self.pos + n].matches('\n').count();
+        self.pos += n;
+    }
+
+    fn push(&mut self, kind: Kind, start: usize, line: usize) {
+        self.tokens.push(Token {
+            kind,
+            span: start..self.pos,
+            line,
+        });
+    }
+
+    fn lex(&mut self) -> Result<(), ParseError> {
+        let mut at_line_start = true;
+        while self.pos < self.src.len() {
+            if at_line_start && self.depth == 0 {
+                at_line_start = false;
+                let line_end = self.rest().find('\n').unwrap_or(self.rest().len());
+                let line = &self.rest()[..line_end];
+                if line.trim().is_empty() {
+                    // Blank lines are kept as newlines so that recipe bodies can span them.
+                    let (start, l) = (self.pos, self.line);
+                    self.advance(line_end);
+                    if self.peek() == Some('\n') {
+                        self.advance(1);
+                    }
+                    self.push(Kind::Newline, start, l);
+                    at_line_start = true;
+                    continue;
+                }
+                if line.starts_with([' ', '\t']) {
+                    let (start, l) = (self.pos, self.line);
+                    self.advance(line_end);
+                    self.push(
+                        Kind::Text(line.trim_end_matches('\r').to_string()),
+                        start,
+                        l,
+                    );
+                    if self.peek() == Some('\n') {
+                        let (start, l) = (self.pos, self.line);
+                        self.advance(1);
+                        self.push(Kind::Newline, start, l);
+                    }
+                    at_line_start = true;
+                    continue;
+                }
+            }
+
+            let c = self.peek().unwrap();
+            let (start, line) = (self.pos, self.line);
+            match c {
+                '\n' => {
+                    self.advance(1);
+                    if self.depth == 0 {
+                        self.push(Kind::Newline, start, line);
+                        at_line_start = true;
+                    }
+                }
+                ' ' | '\t' | '\r' => self.advance(1),
+                // Line continuation.
+                '\\' if self.rest()[1..].trim_start_matches('\r').starts_with('\n') => {
+                    let n = self.rest().find('\n').unwrap() + 1;
+                    self.advance(n);
+                }
+                '#' => {
+                    let n = self.rest().find('\n').unwrap_or(self.rest().len());
+                    let text = self.rest()[1..n].trim().to_string();
+                    self.advance(n);
+                    self.push(Kind::Comment(text), start, line);
+                }
+                '\'' | '"' => {
+                    let value = self.string()?;
+                    self.push(Kind::Str(value), start, line);
+                }
+                '`' => {
+                    let value = self.backtick()?;
+                    self.push(Kind::Backtick(value), start, line);
+                }
+                c if is_name_start(c) => {
+                    let n = self
+                        .rest()
+                        .find(|c| !is_name_continue(c))
+                        .unwrap_or(self.rest().len());
+                    let name = &self.rest()[..n];
+                    // Shell-expanded (x'..') and format (f'..') strings.
+                    if (name == "x" || name == "f") && self.rest()[n..].starts_with(['\'', '"']) {
+                        self.advance(n);
+                        let value = self.string()?;
+                        self.push(Kind::Str(value), start, line);
+                    } else {
+                        let name = name.to_string();
+                        self.advance(n);
+                        self.push(Kind::Name(name), start, line);
+                    }
+                }
+                _ => {
+                    let (kind, n) = match self.rest().as_bytes() {
+                        [b':', b'=', ..] => (Kind::ColonEq, 2),
+                        [b'=', b'=', ..] => (Kind::EqEq, 2),
+                        [b'!', b'=', ..] => (Kind::BangEq, 2),
+                        [b'!', b'~', ..] => (Kind::BangTilde, 2),
+                        [b'=', b'~', ..] => (Kind::EqTilde, 2),
+                        [b'&', b'&', ..] => (Kind::AmpAmp, 2),
+                        [b'|', b'|', ..] => (Kind::BarBar, 2),
+                        [b':', ..] => (Kind::Colon, 1),
+                        [b',', ..] => (Kind::Comma, 1),
+                        [b'=', ..] => (Kind::Eq, 1),
+                        [b'+', ..] => (Kind::Plus, 1),
+                        [b'*', ..] => (Kind::Star, 1),
+                        [b'/', ..] => (Kind::Slash, 1),
+                        [b'$', ..] => (Kind::Dollar, 1),
+                        [b'@', ..] => (Kind::At, 1),
+                        [b'?', ..] => (Kind::Question, 1),
+                        [b'(' | b'[' | b'{', ..] => {
+                            self.depth += 1;
+                            let kind = match c {
+                                '(' => Kind::LParen,
+                                '[' => Kind::LBracket,
+                                _ => Kind::LBrace,
+                            };
+                            (kind, 1)
+                        }
+                        [b')' | b']' | b'}', ..] => {
+                            if self.depth == 0 {
+                                return self.error(format!("unmatched `{c}`"));
+                            }
+                            self.depth -= 1;
+                            let kind = match c {
+                                ')' => Kind::RParen,
+                                ']' => Kind::RBracket,
+                                _ => Kind::RBrace,
+                            };
+                            (kind, 1)
+                        }
+                        _ => return self.error(format!("unexpected character `{c}`")),
+                    };
+                    self.advance(n);
+                    self.push(kind, start, line);
+                }
+            }
+        }
+        if self.depth != 0 {
+            return self.error("unclosed delimiter at end of file");
+        }
+        let end = self.src.len();
+        self.tokens.push(Token {
+            kind: Kind::Eof,
+            span: end..end,
+            line: self.line,
+        });
+        Ok(())
+    }
+
+    // The `{1F600}` of a `\u{1F600}` escape, after the `\u`.
+    fn unicode_escape(&mut self) -> Result<char, ParseError> {
+        let rest = self.rest();
+        let digits = rest
+            .strip_prefix('{')
+            .and_then(|r| r.split_once('}'))
+            .map(|(digits, _)| digits)
+            .filter(|d| (1..=6).contains(&d.len()) && d.chars().all(|c| c.is_ascii_hexdigit()));
+        let Some(digits) = digits else {
+            return self.error("expected `{` and 1 to 6 hex digits and `}` after `\\u`");
+        };
+        let c = u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32);
+        let Some(c) = c else {
+            return self.error(format!("invalid unicode character `\\u{{{digits}}}`"));
+        };
+        self.advance(digits.len() + 2);
+        Ok(c)
+    }
+
+    // Lex a single, double, or triple quoted string starting at the current position.
+    fn string(&mut self) -> Result<String, ParseError> {
+        let quote = self.peek().unwrap();
+        let delim = if self.rest().starts_with(&quote.to_string().repeat(3)) {
+            quote.to_string().repeat(3)
+        } else {
+            quote.to_string()
+        };
+        let line = self.line;
+        self.advance(delim.len());
+        let mut value = String::new();
+        loop {
+            if self.rest().starts_with(&delim) {
+                self.advance(delim.len());
+                break;
+            }
+            let Some(c) = self.peek() else {
+                return Err(ParseError {
+                    line,
+                    message: "unterminated string".into(),
+                });
+            };
+            if c == '\\' && quote == '"' {
+                self.advance(1);
+                let Some(e) = self.peek() else {
+                    continue;
+                };
+                self.advance(e.len_utf8());
+                match e {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    'r' => value.push('\r'),
+                    '\\' => value.push('\\'),
+                    '"' => value.push('"'),
+                    'u' => value.push(self.unicode_escape()?),
+                    '\n' => {
+                        // Escaped newline: skip leading whitespace on the next line.
+                        let n =
+                            self.rest().len() - self.rest().trim_start_matches([' ', '\t']).len();
+                        self.advance(n);
+                    }
+                    e => {
+                        return self.error(format!("invalid escape sequence `\\{e}`"));
+                    }
+                }
+                continue;
+            }
+            value.push(c);
+            self.advance(c.len_utf8());
+        }
+        if delim.len() == 3 {
+            value = dedent(&value);
+        }
+        Ok(value)
+    }
+
+    fn backtick(&mut self) -> Result<String, ParseError> {
+        let delim = if self.rest().starts_with("```") {
+            "```"
+        } else {
+            "`"
+        };
+        let line = self.line;
+        self.advance(delim.len());
+        let Some(n) = self.rest().find(delim) else {
+            return Err(ParseError {
+                line,
+                message: "unterminated backtick".into(),
+            });
+        };
+        let mut value = self.rest()[..n].to_string();
+        self.advance(n + delim.len());
+        if delim.len() == 3 {
+            value = dedent(&value);
+        }
+        Ok(value)
+    }
+}
+
+// Strip a leading newline and the common indentation of all non-blank lines.
+pub fn dedent(s: &str) -> String {
+    let s = s.strip_prefix('\n').unwrap_or(s);
+    let indent = s
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    s.lines()
+        .map(|l| l.get(indent..).unwrap_or(l.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
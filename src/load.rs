@@ -0,0 +1,337 @@
+// Load a justfile with the built-in parser, following its imports and submodules.
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::justfile::{Justfile, Module};
+use crate::{parser, search};
+
+pub fn parse(path: &Path) -> Result<Justfile, String> {
+    parse_file(path, &mut Vec::new(), &mut HashSet::new())
+}
+
+// `just --dump` does not say where rules are defined: take that from the parser, for the
+// rules it finds.
+pub fn locate(justfile: &mut Justfile, path: &Path) {
+    if let Ok(parsed) = parse(path) {
+        copy_locations(justfile, &parsed);
+    }
+}
+
+fn copy_locations(justfile: &mut Justfile, parsed: &Justfile) {
+    for rule in &mut justfile.rules {
+        if let Some(parsed) = parsed.rule(&rule.name) {
+            rule.source = parsed.source.clone();
+            rule.line = parsed.line;
+        }
+    }
+    for module in &mut justfile.modules {
+        if let Some(parsed) = parsed.modules.iter().find(|m| m.name == module.name) {
+            copy_locations(&mut module.justfile, &parsed.justfile);
+        }
+    }
+}
+
+// The justfile and all files it imports or loads as modules, for the watcher. This
+// includes optional imports and modules that do not exist yet, so that creating them is
+// noticed. The files are scanned for `import` and `mod` lines rather than parsed, so the
+// list does not depend on the parser understanding them.
+pub fn sources(path: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_sources(path, &mut HashSet::new(), &mut files);
+    files
+}
+
+fn collect_sources(path: &Path, seen: &mut HashSet<PathBuf>, files: &mut Vec<PathBuf>) {
+    if !seen.insert(path.canonicalize().unwrap_or_else(|_| path.to_path_buf())) {
+        return;
+    }
+    files.push(path.to_path_buf());
+    let Ok(src) = std::fs::read_to_string(path) else {
+        return;
+    };
+    let dir = path.parent().unwrap();
+    for line in src.lines() {
+        let Some((keyword, rest)) = line.split_once([' ', '\t']) else {
+            continue;
+        };
+        match keyword {
+            "import" | "import?" => {
+                if let Some(import) = quoted(rest) {
+                    collect_sources(&import_path(dir, import), seen, files);
+                }
+            }
+            "mod" | "mod?" => {
+                let rest = rest.trim_start();
+                let name_end = rest.find([' ', '\t']).unwrap_or(rest.len());
+                let module = Module {
+                    name: rest[..name_end].to_string(),
+                    path: quoted(&rest[name_end..]).map(str::to_string),
+                    optional: keyword == "mod?",
+                    attributes: Vec::new(),
+                    justfile: Justfile::default(),
+                };
+                match module_path(dir, &module) {
+                    Ok(Some(path)) => collect_sources(&path, seen, files),
+                    // Where the module would most likely be created.
+                    _ => match &module.path {
+                        Some(path) => files.push(dir.join(path)),
+                        None => files.push(dir.join(format!("{}.just", module.name))),
+                    },
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// The contents of a string literal at the start of `s`, without escapes.
+fn quoted(s: &str) -> Option<&str> {
+    let s = s.trim_start();
+    let quote = s.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let rest = &s[1..];
+    Some(&rest[..rest.find(quote)?])
+}
+
+// `stack` holds the files currently being loaded, to detect cycles.
+// `imported` holds the files already merged into the current module, so that a file
+// imported along two paths is only merged once.
+fn parse_file(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    imported: &mut HashSet<PathBuf>,
+) -> Result<Justfile, String> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+    if let Some(i) = stack.iter().position(|p| *p == canonical) {
+        let cycle: Vec<_> = stack[i..]
+            .iter()
+            .chain([&canonical])
+            .map(|p| p.display().to_string())
+            .collect();
+        return Err(format!("circular import: {}", cycle.join(" -> ")));
+    }
+    imported.insert(canonical.clone());
+    stack.push(canonical);
+
+    let src = std::fs::read_to_string(path)
+        .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+    let mut justfile = parser::parse(&src).map_err(|err| format!("{}: {err}", path.display()))?;
+    for rule in &mut justfile.rules {
+        rule.source = Some(path.to_path_buf());
+    }
+
+    let dir = path.parent().unwrap();
+    let mut modules = Vec::new();
+    for mut module in std::mem::take(&mut justfile.modules) {
+        match module_path(dir, &module)? {
+            Some(path) => {
+                module.justfile = parse_file(&path, stack, &mut HashSet::new())?;
+                modules.push(module);
+            }
+            None if module.optional => {}
+            None => {
+                return Err(format!(
+                    "{}: could not find source file for module `{}`",
+                    path.display(),
+                    module.name
+                ));
+            }
+        }
+    }
+    justfile.modules = modules;
+
+    for import in justfile.imports.clone() {
+        let import_path = import_path(dir, &import.path);
+        if !import_path.is_file() {
+            if import.optional {
+                continue;
+            }
+            return Err(format!(
+                "{}: could not find imported file {}",
+                path.display(),
+                import_path.display()
+            ));
+        }
+        let canonical = import_path
+            .canonicalize()
+            .map_err(|err| format!("could not read {}: {err}", import_path.display()))?;
+        if imported.contains(&canonical) && !stack.contains(&canonical) {
+            continue;
+        }
+        let other = parse_file(&import_path, stack, imported)?;
+        justfile.rules.extend(other.rules);
+        justfile.aliases.extend(other.aliases);
+        justfile.assignments.extend(other.assignments);
+        justfile.settings.extend(other.settings);
+        justfile.modules.extend(other.modules);
+    }
+
+    stack.pop();
+    Ok(justfile)
+}
+
+// Imports are relative to the importing file, and may start with `~/`.
+fn import_path(dir: &Path, path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(home) = std::env::var_os("HOME")
+    {
+        return PathBuf::from(home).join(rest);
+    }
+    dir.join(path)
+}
+
+// The source file of a module, searched for like just does:
+// `mod foo` loads `foo.just`, `foo/mod.just`, or `foo/justfile`,
+// and `mod foo 'path'` loads `path`, or the justfile in directory `path`.
+fn module_path(dir: &Path, module: &Module) -> Result<Option<PathBuf>, String> {
+    if let Some(path) = &module.path {
+        let path = dir.join(path);
+        if path.is_dir() {
+            return Ok(search::candidates(&path)?.into_iter().next());
+        }
+        return Ok(path.is_file().then_some(path));
+    }
+    let file = dir.join(format!("{}.just", module.name));
+    if file.is_file() {
+        return Ok(Some(file));
+    }
+    let module_dir = dir.join(&module.name);
+    if !module_dir.is_dir() {
+        return Ok(None);
+    }
+    let file = module_dir.join("mod.just");
+    if file.is_file() {
+        return Ok(Some(file));
+    }
+    Ok(search::candidates(&module_dir)?.into_iter().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory with the given files, e.g. `("sub/mod.just", "build:\n")`.
+    fn tree(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("just-shell-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn sources() {
+        let dir = tree(
+            "sources",
+            &[
+                (
+                    "justfile",
+                    "import 'a.just'\nimport? \"local.just\"\nmod sub\nmod? tools\n\nx := 1 !~ '2'\n",
+                ),
+                ("a.just", "import 'justfile'\n"),
+                ("sub/mod.just", "build:\n"),
+            ],
+        );
+        let files: Vec<_> = super::sources(&dir.join("justfile"))
+            .iter()
+            .map(|f| f.strip_prefix(&dir).unwrap().to_path_buf())
+            .collect();
+        // Missing optional files are included; the cycle back to the justfile is not.
+        let expected = [
+            "justfile",
+            "a.just",
+            "local.just",
+            "sub/mod.just",
+            "tools.just",
+        ];
+        assert_eq!(files, expected.map(PathBuf::from));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn rules(justfile: &Justfile) -> Vec<&str> {
+        justfile.rules.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    #[test]
+    fn cycle() {
+        let dir = tree(
+            "cycle",
+            &[
+                ("justfile", "import 'a.just'\n"),
+                ("a.just", "import 'b.just'\n"),
+                ("b.just", "import 'a.just'\n"),
+            ],
+        );
+        let err = parse(&dir.join("justfile")).unwrap_err();
+        let cycle: Vec<_> = err
+            .strip_prefix("circular import: ")
+            .unwrap()
+            .split(" -> ")
+            .map(|p| Path::new(p).file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(cycle, ["a.just", "b.just", "a.just"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn optional_import() {
+        let dir = tree(
+            "optional",
+            &[("justfile", "import? 'local.just'\nbuild:\n  echo\n")],
+        );
+        let justfile = parse(&dir.join("justfile")).unwrap();
+        assert_eq!(rules(&justfile), ["build"]);
+        // Without the `?`, the missing file is an error.
+        std::fs::write(dir.join("justfile"), "import 'local.just'\n").unwrap();
+        let err = parse(&dir.join("justfile")).unwrap_err();
+        assert!(err.contains("could not find imported file"), "{err}");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn diamond() {
+        let dir = tree(
+            "diamond",
+            &[
+                ("justfile", "import 'a.just'\nimport 'b.just'\nbuild:\n"),
+                ("a.just", "import 'common.just'\na:\n"),
+                ("b.just", "import 'common.just'\nb:\n"),
+                ("common.just", "common:\n"),
+            ],
+        );
+        let justfile = parse(&dir.join("justfile")).unwrap();
+        // The shared file is merged once.
+        assert_eq!(rules(&justfile), ["build", "a", "common", "b"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rule_sources() {
+        let dir = tree(
+            "rule_sources",
+            &[
+                (
+                    "justfile",
+                    "import 'ci/ci.just'\nmod tools\n\nbuild:\n  echo\n",
+                ),
+                ("ci/ci.just", "# Run the tests\ntest:\n  echo\n"),
+                ("tools.just", "lint:\n  echo\n"),
+            ],
+        );
+        let justfile = parse(&dir.join("justfile")).unwrap();
+        let location = |justfile: &Justfile, name: &str| {
+            let rule = justfile.rule(name).unwrap();
+            let source = rule.source.as_ref().unwrap();
+            (source.strip_prefix(&dir).unwrap().to_path_buf(), rule.line)
+        };
+        assert_eq!(location(&justfile, "build"), ("justfile".into(), Some(4)));
+        assert_eq!(location(&justfile, "test"), ("ci/ci.just".into(), Some(2)));
+        let tools = &justfile.modules[0].justfile;
+        assert_eq!(location(tools, "lint"), ("tools.just".into(), Some(1)));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
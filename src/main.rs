@@ -1,78 +1,245 @@
-#![allow(unused)]
-#![feature(let_chains, ascii_char)]
-use std::{fs::File, io::Read, path::Path, process::Command};
+use std::cell::RefCell;
+use std::os::unix::process::ExitStatusExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{path::Path, process::Command};
 
 use colored::Colorize;
-use fuzzy_matcher::FuzzyMatcher;
-use rustyline::hint::Hinter;
+use rustyline::completion::FilenameCompleter;
+use rustyline::hint::{Hint, Hinter};
+use rustyline::{Cmd, ConditionalEventHandler, Event, EventContext, EventHandler, Movement};
+use rustyline::{Helper, Highlighter, Validator};
+use rustyline::{KeyCode, KeyEvent, Modifiers, RepeatCount};
 use rustyline::{error::ReadlineError, history::DefaultHistory};
-use rustyline::{Completer, Helper, Highlighter, Validator};
 
-type Matcher = fuzzy_matcher::skim::SkimMatcherV2;
+mod args;
+mod builtin;
+mod cli;
+mod complete;
+mod confirm;
+mod dump;
+mod justfile;
+mod lexer;
+mod load;
+mod parser;
+mod picker;
+mod process;
+mod prompt;
+mod search;
+mod show;
+mod state;
+mod times;
+mod usage;
+mod watch;
 
-// Rule has one of the forms:
-// - <rule>: [deps]
-// - <rule> <arg1> ...: [deps]
-struct Rule {
-    name: String,
-    args: Vec<String>,
+use args::ArgHistory;
+use cli::OnEmpty;
+use justfile::{Justfile, Match};
+use search::Search;
+use times::{Run, Times};
+use usage::Usage;
+
+// Maximum number of lines kept in the history file.
+const HISTORY_SIZE: usize = 1000;
+// Runs taking longer than this show their duration in the prompt.
+const SHOW_DURATION: Duration = Duration::from_secs(1);
+
+#[derive(Helper, Validator, Highlighter)]
+struct MyHinter<'j> {
+    justfile: &'j RefCell<Justfile>,
+    usage: &'j RefCell<Usage>,
+    // Toggled with Alt-p.
+    show_private: Arc<AtomicBool>,
+    // Toggled with Alt-d: rules are run with `just --dry-run`.
+    dry_run: Arc<AtomicBool>,
+    files: FilenameCompleter,
+    cycle: RefCell<Option<complete::Cycle>>,
+    // Set while prompting for a missing argument or a confirmation.
+    arg_prompt: Option<prompt::ArgPrompt>,
+    // Whether `arg_prompt` is set, for the key handlers: the picker and the toggles are
+    // only for the main prompt.
+    in_prompt: Arc<AtomicBool>,
+    arg_history: Arc<Mutex<ArgHistory>>,
+    // The rule whose name has been typed, kept up to date by the hinter for `BrowseArgs`.
+    typed_rule: Arc<Mutex<Option<String>>>,
+    reloaded: watch::Reloaded,
 }
 
-// Alias has the form:
-// alias <alias> := <rule>
-struct Alias {
-    alias: String,
-    rule: String,
+// A hint, with optionally some text that `Right` inserts into the line.
+struct MyHint {
+    display: String,
+    completion: Option<String>,
 }
 
-struct Justfile {
-    rules: Vec<Rule>,
-    aliases: Vec<Alias>,
+impl Hint for MyHint {
+    fn display(&self) -> &str {
+        &self.display
+    }
+
+    fn completion(&self) -> Option<&str> {
+        self.completion.as_deref()
+    }
 }
 
-thread_local! {
-    static MATCHER: Matcher = Matcher::default();
+// Toggle a setting, e.g. whether private rules are included in the matches.
+struct Toggle {
+    flag: Arc<AtomicBool>,
+    in_prompt: Arc<AtomicBool>,
 }
 
-impl Justfile {
-    fn matches(&self, pattern: &str) -> Vec<(&Rule, (i64, Vec<usize>))> {
-        MATCHER.with(|m| {
-            let mut matches: Vec<_> = self
-                .rules
-                .iter()
-                .filter_map(|r| Some((r, m.fuzzy_indices(&r.name, pattern)?)))
-                .collect();
-            matches.sort_by_key(|(_r, (match_score, _indices))| -*match_score);
-            matches
-        })
+impl ConditionalEventHandler for Toggle {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, _: &EventContext) -> Option<Cmd> {
+        if self.in_prompt.load(Ordering::Relaxed) {
+            return Some(Cmd::Noop);
+        }
+        self.flag.fetch_xor(true, Ordering::Relaxed);
+        Some(Cmd::Repaint)
     }
-    fn best_match(&self, pattern: Option<&str>) -> Option<&Rule> {
-        let pattern = pattern.unwrap_or("");
-        if pattern.is_empty() {
-            return self.rules.first();
+}
+
+// Open the picker, with the current line as the query.
+struct OpenPicker {
+    open: Arc<AtomicBool>,
+    in_prompt: Arc<AtomicBool>,
+}
+
+impl ConditionalEventHandler for OpenPicker {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, _: &EventContext) -> Option<Cmd> {
+        // Accepting the line of a prompt for an argument would run the rule.
+        if self.in_prompt.load(Ordering::Relaxed) {
+            return Some(Cmd::Noop);
         }
-        MATCHER.with(|m| {
-            let (_score, rule) = self
-                .rules
-                .iter()
-                .rev()
-                .filter_map(|r| Some((m.fuzzy_match(&r.name, pattern)?, r)))
-                .max_by_key(|&(match_score, _)| match_score)?;
-            Some(rule)
-        })
+        self.open.store(true, Ordering::Relaxed);
+        Some(Cmd::AcceptLine)
     }
 }
 
-#[derive(Helper, Completer, Validator, Highlighter)]
-struct MyHinter<'j> {
-    justfile: &'j Justfile,
+// Once a rule name is typed, without arguments, Up and Down browse the argument lists it
+// was run with instead of the history.
+struct BrowseArgs {
+    arg_history: Arc<Mutex<ArgHistory>>,
+    typed_rule: Arc<Mutex<Option<String>>>,
+    // The line put there by the last browse, shared by Up and Down.
+    browsed: Arc<Mutex<Option<String>>>,
+    older: bool,
+}
+
+impl ConditionalEventHandler for BrowseArgs {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, ctx: &EventContext) -> Option<Cmd> {
+        let rule = self.typed_rule.lock().unwrap().clone()?;
+        let arg_history = self.arg_history.lock().unwrap();
+        let lists = arg_history.get(&rule);
+        if lists.is_empty() {
+            return None;
+        }
+        let (name, args) = ctx.line().trim_start().split_once(char::is_whitespace)?;
+        let mut browsed = self.browsed.lock().unwrap();
+        // Other arguments were typed, or recalled from the history: keep going through it.
+        if !args.trim().is_empty() && browsed.as_deref() != Some(ctx.line()) {
+            return None;
+        }
+        let args: Vec<&str> = args.split_whitespace().collect();
+        let current = match args.is_empty() {
+            true => None,
+            false => lists.iter().rposition(|l| *l == args),
+        };
+        let next = match (current, self.older) {
+            (None, true) => Some(lists.len() - 1),
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (None, false) => return None,
+            // Going past the most recent list clears the arguments.
+            (Some(i), false) => (i + 1 < lists.len()).then_some(i + 1),
+        };
+        let line = match next {
+            Some(i) => format!("{name} {}", lists[i].join(" ")),
+            None => format!("{name} "),
+        };
+        *browsed = Some(line.clone());
+        Some(Cmd::Replace(Movement::WholeLine, Some(line)))
+    }
 }
 
 impl<'j> Hinter for MyHinter<'j> {
-    type Hint = String;
+    type Hint = MyHint;
+
+    fn hint(&self, line: &str, pos: usize, _ctx: &rustyline::Context<'_>) -> Option<MyHint> {
+        let mut hint = self.line_hint(line, pos)?;
+        let command = line.trim_start();
+        if self.arg_prompt.is_none()
+            && !command.starts_with(':')
+            && (command.starts_with('?') || self.dry_run.load(Ordering::Relaxed))
+        {
+            hint.display.push_str(&format!("  {}", "dry run".yellow()));
+        }
+        Some(hint)
+    }
+}
+
+impl MyHinter<'_> {
+    fn line_hint(&self, line: &str, pos: usize) -> Option<MyHint> {
+        let usage = self.usage.borrow();
+        let show_private = self.show_private.load(Ordering::Relaxed);
+        watch::apply(&self.reloaded, self.justfile);
+        let justfile = self.justfile.borrow();
+        let mut typed_rule = self.typed_rule.lock().unwrap();
+        *typed_rule = None;
+
+        // While prompting for an argument, show the values used before.
+        if let Some(arg_prompt) = &self.arg_prompt {
+            let mut values = arg_prompt.candidates(line);
+            values.retain(|v| *v != line);
+            if values.is_empty() {
+                return None;
+            }
+            let hint = format!(" ({})", values.join(", ")).dimmed().to_string();
+            return Some(MyHint {
+                display: hint,
+                completion: None,
+            });
+        }
 
-    fn hint(&self, line: &str, pos: usize, _ctx: &rustyline::Context<'_>) -> Option<String> {
-        let matches = self.justfile.matches(line);
+        if let Some(command) = line.trim_start().strip_prefix(':') {
+            let hint = self.builtin_hint(command)?;
+            return Some(MyHint {
+                display: hint,
+                completion: None,
+            });
+        }
+
+        // `?rule` is a dry run of the rule, hinted like the rule itself.
+        let (line, pos) = match line.trim_start().strip_prefix('?') {
+            Some(rest) => (rest, pos.saturating_sub(line.len() - rest.len())),
+            None => (line, pos),
+        };
+
+        // Once the rule is typed, show its parameters instead.
+        if let Some((pattern, args)) = line.trim_start().split_once(char::is_whitespace) {
+            let m = justfile.best_match(Some(pattern), &usage, show_private)?;
+            *typed_rule = Some(m.path.clone());
+            // Suggest the arguments the rule was last run with.
+            let arg_history = self.arg_history.lock().unwrap();
+            if args.trim().is_empty()
+                && pos == line.len()
+                && let Some(last) = arg_history.last(&m.path)
+            {
+                let mut ghost = last.join(" ");
+                if !line.ends_with(char::is_whitespace) {
+                    ghost.insert(0, ' ');
+                }
+                let params = self.hint_params(&m, "", pos + ghost.len());
+                return Some(MyHint {
+                    display: format!("{}{params}", ghost.dimmed()),
+                    completion: Some(ghost),
+                });
+            }
+            return Some(MyHint {
+                display: self.hint_params(&m, args, pos),
+                completion: None,
+            });
+        }
+
+        let matches = justfile.matches(line, &usage, show_private);
         if matches.is_empty() {
             return None;
         }
@@ -83,20 +250,33 @@ impl<'j> Hinter for MyHinter<'j> {
 
         let offset = (pos + 1).next_multiple_of(10);
         let cols = termion::terminal_size().unwrap().0;
-        let max_len = cols as usize - offset - 13;
+        let available = (cols as usize).saturating_sub(offset + 13);
+        // Leave room for the documentation of the top match.
+        let doc = matches[0].rule.doc.as_deref();
+        let max_len = match doc {
+            Some(_) => available * 2 / 3,
+            None => available,
+        };
 
-        for (rule, (_score, match_positions)) in &matches[..matches.len()] {
+        for m in &matches {
+            let name = &m.name;
+            // Aliases are shown together with their target.
+            let target = match m.alias {
+                Some(_) => format!(" → {}", m.path),
+                None => String::new(),
+            };
+            let width = name.chars().count() + target.chars().count();
             if !first {
                 s.push_str(", ");
             }
-            if len + rule.name.len() >= max_len {
+            if len + width >= max_len {
                 s.push_str("...");
                 break;
             }
-            len += rule.name.len() + 2;
+            len += width + 2;
             let mut j = 0;
-            for (i, c) in rule.name.chars().enumerate() {
-                if match_positions.get(j) == Some(&i) {
+            for (i, c) in name.chars().enumerate() {
+                if m.indices.get(j) == Some(&i) {
                     if first {
                         s.push_str(&format!("{}", c.to_string().bold().underline().green()));
                     } else {
@@ -111,28 +291,214 @@ impl<'j> Hinter for MyHinter<'j> {
                     }
                 }
             }
+            if first {
+                s.push_str(&format!("{}", target.green()));
+            } else {
+                s.push_str(&target);
+            }
             first = false;
         }
 
         let padding = offset - pos;
-        Some(format!(" {:>padding$}({s})", "", padding = padding))
+        let mut hint = format!(" {:>padding$}({s})", "", padding = padding);
+        if let Some(doc) = doc {
+            let width = available.saturating_sub(len.min(max_len));
+            let doc = show::fit(doc.lines().next().unwrap_or_default(), width);
+            if width > 0 {
+                hint.push_str(&format!(" {}", doc.dimmed().italic()));
+            }
+        }
+        Some(MyHint {
+            display: hint,
+            completion: None,
+        })
+    }
+
+    pub fn show_private(&self) -> bool {
+        self.show_private.load(Ordering::Relaxed)
+    }
+
+    // The signature of the rule, e.g. `build <target> [profile=release] <files...>`,
+    // with the parameter currently being typed highlighted.
+    fn hint_params(&self, m: &Match, args: &str, pos: usize) -> String {
+        let params = &m.rule.params;
+        let mut typed = args.split_whitespace().count();
+        // While typing a word, the current parameter is the one for that word.
+        if !args.is_empty() && !args.ends_with(char::is_whitespace) {
+            typed -= 1;
+        }
+        let current = match params.last() {
+            Some(last) if last.variadic.is_some() => typed.min(params.len() - 1),
+            _ => typed,
+        };
+
+        let mut s = m.path.green().to_string();
+        for (i, param) in params.iter().enumerate() {
+            let usage = param.usage();
+            let usage = if i == current {
+                usage.bold().underline().green()
+            } else if i < current {
+                usage.dimmed()
+            } else if param.required() {
+                usage.bold()
+            } else {
+                usage.normal()
+            };
+            s.push_str(&format!(" {usage}"));
+        }
+        if current >= params.len() && params.last().is_none_or(|p| p.variadic.is_none()) {
+            s.push_str(&format!(" {}", "too many arguments".red()));
+        } else {
+            let missing: Vec<_> = params
+                .iter()
+                .skip(current + 1)
+                .filter(|p| p.required())
+                .map(|p| p.usage())
+                .collect();
+            if !missing.is_empty() {
+                s.push_str(&format!(
+                    " {}",
+                    format!("needs {}", missing.join(" ")).dimmed()
+                ));
+            }
+        }
+
+        let offset = (pos + 1).next_multiple_of(10);
+        let padding = offset - pos;
+        format!(" {:>padding$}{s}", "", padding = padding)
     }
 }
 
 fn main() {
+    // Recipes run as foreground jobs that receive Ctrl-C themselves. This only keeps a
+    // Ctrl-C from killing the shell while it is busy itself, e.g. loading the justfile.
     ctrlc::set_handler(|| {}).unwrap();
-    let justfile = read();
+    process::init();
+    let args = match cli::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}", format!("Error: {err}.").bold().red());
+            std::process::exit(1);
+        }
+    };
+    let cwd = std::env::current_dir().unwrap();
+    let search = match &args.justfile {
+        Some(justfile) => search::explicit(&cwd, justfile, args.working_directory.as_deref()),
+        None => search::find(&cwd),
+    };
+    let search = match search {
+        Ok(search) => search,
+        Err(err) => {
+            eprintln!("{}", format!("Error: {err}.").bold().red());
+            std::process::exit(1);
+        }
+    };
+    let justfile = match read(&search) {
+        Ok((justfile, source)) => {
+            print_source(&search, &source);
+            RefCell::new(justfile)
+        }
+        Err(err) => {
+            eprintln!("{}", format!("Error: {err}").bold().red());
+            std::process::exit(1);
+        }
+    };
 
-    let mut rl = rustyline::Editor::<MyHinter, DefaultHistory>::new().unwrap();
+    let config = rustyline::Config::builder()
+        .max_history_size(HISTORY_SIZE)
+        .unwrap()
+        .history_ignore_dups(true)
+        .unwrap()
+        .completion_type(rustyline::CompletionType::List)
+        .build();
+    let mut rl = rustyline::Editor::<MyHinter, DefaultHistory>::with_config(config).unwrap();
+    let history_path = state::project_file(&search.justfile, "history");
+    if let Some(path) = &history_path
+        && path.exists()
+        && let Err(err) = rl.load_history(path)
+    {
+        eprintln!("{}", format!("Could not load history: {err}").dimmed());
+    }
+    let usage = RefCell::new(Usage::load(&search.justfile));
+    let mut times = Times::load(&search.justfile);
+    let show_private = Arc::new(AtomicBool::new(false));
+    let dry_run = Arc::new(AtomicBool::new(false));
+    let arg_history = Arc::new(Mutex::new(ArgHistory::load(&search.justfile)));
+    let typed_rule = Arc::new(Mutex::new(None));
+    let open_picker = Arc::new(AtomicBool::new(args.choose));
+    let protect = args.protect;
+    let on_empty = args.on_empty;
+    let reloaded = watch::Reloaded::default();
+    let in_prompt = Arc::new(AtomicBool::new(false));
     rl.set_helper(Some(MyHinter {
         justfile: &justfile,
+        usage: &usage,
+        show_private: show_private.clone(),
+        dry_run: dry_run.clone(),
+        files: FilenameCompleter::new(),
+        cycle: RefCell::new(None),
+        arg_prompt: None,
+        in_prompt: in_prompt.clone(),
+        arg_history: arg_history.clone(),
+        typed_rule: typed_rule.clone(),
+        reloaded: reloaded.clone(),
     }));
+    watch::spawn(search.clone(), &justfile.borrow(), reloaded.clone());
 
     rl.bind_sequence(rustyline::KeyEvent::ctrl('k'), rustyline::Cmd::AcceptLine);
+    rl.bind_sequence(
+        rustyline::KeyEvent::alt('p'),
+        EventHandler::Conditional(Box::new(Toggle {
+            flag: show_private.clone(),
+            in_prompt: in_prompt.clone(),
+        })),
+    );
+    rl.bind_sequence(
+        KeyEvent::alt('d'),
+        EventHandler::Conditional(Box::new(Toggle {
+            flag: dry_run.clone(),
+            in_prompt: in_prompt.clone(),
+        })),
+    );
+    rl.bind_sequence(
+        KeyEvent::ctrl('t'),
+        EventHandler::Conditional(Box::new(OpenPicker {
+            open: open_picker.clone(),
+            in_prompt: in_prompt.clone(),
+        })),
+    );
+    let browsed = Arc::new(Mutex::new(None));
+    for (key, older) in [(KeyCode::Up, true), (KeyCode::Down, false)] {
+        rl.bind_sequence(
+            KeyEvent(key, Modifiers::NONE),
+            EventHandler::Conditional(Box::new(BrowseArgs {
+                arg_history: arg_history.clone(),
+                typed_rule: typed_rule.clone(),
+                browsed: browsed.clone(),
+                older,
+            })),
+        );
+    }
 
-    let prompt = format!("{}> ", "Just".bold().red());
+    // The status of the last run in this session, shown in the prompt.
+    let mut status = None;
+    // Text to start the next line with, e.g. the rule chosen in the picker.
+    let mut initial: Option<String> = None;
     loop {
-        let line = rl.readline(&prompt);
+        for notice in watch::notices(&reloaded) {
+            eprintln!("{notice}");
+        }
+        let prompt = match &status {
+            Some(status) => format!("{status} {}> ", "Just".bold().red()),
+            None => format!("{}> ", "Just".bold().red()),
+        };
+        let line = if open_picker.load(Ordering::Relaxed) {
+            Ok(String::new())
+        } else if let Some(initial) = initial.take() {
+            rl.readline_with_initial(&prompt, (&initial, ""))
+        } else {
+            rl.readline(&prompt)
+        };
         let line = match line {
             Ok(line) => line,
             Err(ReadlineError::Interrupted | ReadlineError::Eof) => {
@@ -143,72 +509,248 @@ fn main() {
                 break;
             }
         };
-        let mut args = line.split_whitespace();
-        let rule = justfile.best_match(args.next()).unwrap();
+        watch::apply(&reloaded, &justfile);
 
-        let r = run(rule, args);
-        if r.success() {
-            rl.add_history_entry(&line).unwrap();
-        } else {
-            eprintln!(
-                "! exit code: {}",
-                r.code().unwrap().to_string().bold().red()
-            );
+        if open_picker.swap(false, Ordering::Relaxed) {
+            let query = line.split_whitespace().next().unwrap_or_default();
+            let show_private = show_private.load(Ordering::Relaxed);
+            let chosen = picker::pick(&justfile.borrow(), &usage.borrow(), show_private, query);
+            // Put the chosen rule on the line, so that arguments can be added.
+            initial = Some(chosen.map_or(line, |path| format!("{path} ")));
+            continue;
         }
-    }
-}
 
-fn read() -> Justfile {
-    let justfile_path = Path::new("justfile");
-    let mut justfile = String::new();
-    let Ok(mut file) = File::open(justfile_path) else {
-        eprintln!(
-            "{}",
-            "Error: no justfile in current directory.".bold().red()
-        );
-        std::process::exit(1);
-    };
-    file.read_to_string(&mut justfile).unwrap();
+        // Lines starting with `:` are commands of the shell itself.
+        if let Some(command) = line.trim_start().strip_prefix(':') {
+            add_history(&mut rl, history_path.as_deref(), &line);
+            let ctx = builtin::Context {
+                search: &search,
+                justfile: &justfile,
+                usage: &usage,
+                history: rl.history(),
+                times: &times,
+                show_private: show_private.load(Ordering::Relaxed),
+            };
+            if builtin::run(&ctx, command.trim_end()).is_break() {
+                break;
+            }
+            continue;
+        }
+
+        // `?rule` only prints what the rule would run, as does every rule in dry-run mode.
+        let (command, prefixed) = match line.trim_start().strip_prefix('?') {
+            Some(command) => (command, true),
+            None => (line.as_str(), false),
+        };
+        let dry_run = prefixed || dry_run.load(Ordering::Relaxed);
+
+        // An empty line runs the default rule, reruns the last command or does nothing.
+        let last;
+        let rerun = on_empty == OnEmpty::Last && command.trim().is_empty();
+        let command = match on_empty {
+            _ if !command.trim().is_empty() => command,
+            OnEmpty::Default => command,
+            OnEmpty::Nothing => continue,
+            OnEmpty::Last => {
+                let Some(run) = times.runs.last() else {
+                    eprintln!("{}", "Nothing to rerun yet.".dimmed());
+                    continue;
+                };
+                let command = std::iter::once(&run.path).chain(&run.args);
+                let command: Vec<_> = command.map(String::as_str).collect();
+                last = command.join(" ");
+                eprintln!("{}", last.dimmed());
+                &last
+            }
+        };
 
-    let mut rules = Vec::new();
-    let mut aliases = Vec::new();
+        let justfile = justfile.borrow();
+        let mut args = command.split_whitespace();
+        let pattern = args.next();
+        let Some(m) = justfile.best_match(
+            pattern,
+            &usage.borrow(),
+            show_private.load(Ordering::Relaxed),
+        ) else {
+            match pattern {
+                Some(pattern) => no_match(&justfile, pattern, show_private.load(Ordering::Relaxed)),
+                None => eprintln!("{}", "Error: the justfile has no recipes.".bold().red()),
+            }
+            continue;
+        };
+        let rule = m.rule;
+        let mut args: Vec<String> = args.map(str::to_string).collect();
 
-    for line in justfile.lines() {
-        if line.is_empty() {
+        let (min, max) = rule.arity();
+        if max.is_some_and(|max| args.len() > max) {
+            eprintln!("! usage: {}", rule.signature(&m.path).bold());
             continue;
         }
-        if let Some(line) = line.strip_prefix("alias ") {
-            if let Some((alias, rule)) = line.split_once(":=") {
-                aliases.push(Alias {
-                    alias: alias.trim().to_string(),
-                    rule: rule.trim().to_string(),
-                });
+        if args.len() < min && !prompt::missing_args(&mut rl, &arg_history, &m, &mut args) {
+            continue;
+        }
+
+        if dry_run {
+            // Nothing runs, so there is nothing to confirm.
+            if let Err(err) = run(&search, &["--dry-run", "--yes"], &m.path, &args) {
+                eprintln!(
+                    "{}",
+                    format!("Error: could not run just: {err}").bold().red()
+                );
             }
+            add_history(&mut rl, history_path.as_deref(), &line);
             continue;
         }
-        if let Some((line, _deps)) = line.split_once(':')
-            && !line.starts_with(' ')
-        {
-            let mut parts = line.split_whitespace();
-            let name = parts.next().unwrap().trim().to_string();
-            let args = parts.map(|s| s.to_string()).collect();
-            rules.push(Rule { name, args });
+
+        let mut flags = Vec::new();
+        // A rerun was not typed, so protected rules ask anyway.
+        let typed = pattern.filter(|_| !rerun);
+        let questions = confirm::questions(&justfile, &m, typed, &args, &protect);
+        if !questions.iter().all(|q| confirm::ask(&mut rl, q)) {
+            continue;
+        }
+        // Everything was asked already, so just need not ask again.
+        if !questions.is_empty() {
+            flags.push("--yes");
+        }
+
+        usage.borrow_mut().record(&m.path);
+        let (start, started) = (usage::now(), Instant::now());
+        let r = match run(&search, &flags, &m.path, &args) {
+            Ok(r) => r,
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    format!("Error: could not run just: {err}").bold().red()
+                );
+                continue;
+            }
+        };
+        let run = Run {
+            start,
+            duration: started.elapsed(),
+            status: process::status_label(&r),
+            path: m.path.clone(),
+            args: args.clone(),
+        };
+        status = Some(run_status(&run));
+        times.record(run);
+        if r.success() {
+            arg_history.lock().unwrap().record(&m.path, &args);
+            // Store the prompted arguments as well, so they can be reused.
+            let entry = pattern.into_iter();
+            let entry: Vec<&str> = entry.chain(args.iter().map(String::as_str)).collect();
+            add_history(&mut rl, history_path.as_deref(), &entry.join(" "));
+        } else if let Some(code) = r.code() {
+            let signal = match process::likely_signal(code) {
+                Some(signal) => format!(" ({signal}?)"),
+                None => String::new(),
+            };
+            eprintln!("! exit code: {}{signal}", code.to_string().bold().red());
+        } else if let Some(signal) = r.signal() {
+            let core = if r.core_dumped() {
+                " (core dumped)"
+            } else {
+                ""
+            };
+            let signal = process::signal_name(signal);
+            eprintln!("! killed by {}{core}", signal.bold().red());
         }
     }
+}
 
-    Justfile { rules, aliases }
+// A green check or a red cross with the exit code, and the duration of slow runs.
+fn run_status(run: &Run) -> String {
+    let mut s = match run.success() {
+        true => "✓".green().to_string(),
+        false => format!("✗ {}", run.status).red().to_string(),
+    };
+    if run.duration >= SHOW_DURATION {
+        let duration = times::format_duration(run.duration);
+        s.push_str(&format!(" {}", duration.dimmed()));
+    }
+    s
 }
 
-fn run<I, S>(r: &Rule, args: I) -> std::process::ExitStatus
+// Report a pattern that matches no rule, with the names it may be a typo of.
+pub fn no_match(justfile: &Justfile, pattern: &str, show_private: bool) {
+    eprintln!(
+        "{}",
+        format!("Error: no rule matches `{pattern}`.").bold().red()
+    );
+    let suggestions = justfile.suggestions(pattern, show_private);
+    if !suggestions.is_empty() {
+        let suggestions: Vec<_> = suggestions.iter().map(|s| format!("`{s}`")).collect();
+        eprintln!(
+            "{}",
+            format!("Did you mean {}?", suggestions.join(", ")).dimmed()
+        );
+    }
+}
+
+fn add_history(
+    rl: &mut rustyline::Editor<MyHinter, DefaultHistory>,
+    path: Option<&Path>,
+    line: &str,
+) {
+    rl.add_history_entry(line).unwrap();
+    if let Some(path) = path
+        && let Err(err) = rl.append_history(path)
+    {
+        eprintln!("{}", format!("Could not save history: {err}").dimmed());
+    }
+}
+
+// Where the rules were loaded from.
+enum Source {
+    Dump,
+    // The parser is used when `just --dump` fails, e.g. because just is too old.
+    Parser { dump_error: String },
+}
+
+fn read(search: &Search) -> Result<(Justfile, Source), String> {
+    let dump_error = match dump::load(&search.justfile) {
+        Ok(mut justfile) => {
+            load::locate(&mut justfile, &search.justfile);
+            return Ok((justfile, Source::Dump));
+        }
+        Err(err) => err,
+    };
+    let justfile = load::parse(&search.justfile)?;
+    Ok((justfile, Source::Parser { dump_error }))
+}
+
+fn print_source(search: &Search, source: &Source) {
+    let path = search.justfile.display();
+    match source {
+        Source::Dump => eprintln!("{}", format!("{path} (loaded via `just --dump`)").dimmed()),
+        Source::Parser { dump_error } => eprintln!(
+            "{}",
+            format!("{path} (parsed directly; `just --dump` failed: {dump_error})").dimmed()
+        ),
+    }
+}
+
+// Run the rule with the given `module::rule` path, passing `flags` to just,
+// e.g. `--dry-run` to only print the commands it would run, dependencies included.
+fn run<I, S>(
+    search: &Search,
+    flags: &[&str],
+    path: &str,
+    args: I,
+) -> std::io::Result<std::process::ExitStatus>
 where
     I: IntoIterator<Item = S>,
     S: AsRef<std::ffi::OsStr>,
 {
-    Command::new("just")
-        .arg(&r.name)
-        .args(args)
-        .spawn()
-        .unwrap()
-        .wait()
-        .unwrap()
+    process::run(
+        Command::new("just")
+            .arg("--justfile")
+            .arg(&search.justfile)
+            .arg("--working-directory")
+            .arg(&search.working_directory)
+            .args(flags)
+            .arg(path)
+            .args(args),
+    )
 }
@@ -0,0 +1,687 @@
+use crate::justfile::*;
+use crate::lexer::{Kind, ParseError, Token, lex};
+
+struct Parser<'s> {
+    src: &'s str,
+    tokens: Vec<Token>,
+    pos: usize,
+    // Attributes waiting for the item they apply to.
+    attributes: Vec<Attribute>,
+    // A comment on the line directly before the current item.
+    comment: Option<String>,
+    justfile: Justfile,
+}
+
+pub fn parse(src: &str) -> Result<Justfile, ParseError> {
+    let mut parser = Parser {
+        src,
+        tokens: lex(src)?,
+        pos: 0,
+        attributes: Vec::new(),
+        comment: None,
+        justfile: Justfile::default(),
+    };
+    parser.parse()?;
+    Ok(parser.justfile)
+}
+
+fn is_eol(kind: &Kind) -> bool {
+    matches!(kind, Kind::Newline | Kind::Eof | Kind::Comment(_))
+}
+
+impl<'s> Parser<'s> {
+    fn peek(&self, n: usize) -> &Kind {
+        // The last token is always Eof.
+        &self.tokens[(self.pos + n).min(self.tokens.len() - 1)].kind
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.token().clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            line: self.token().line,
+            message: message.into(),
+        })
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T, ParseError> {
+        let found = match self.peek(0) {
+            Kind::Name(name) => format!("`{name}`"),
+            Kind::Newline => "end of line".to_string(),
+            Kind::Eof => "end of file".to_string(),
+            Kind::Text(_) => "indented line".to_string(),
+            _ => format!("`{}`", &self.src[self.token().span.clone()]),
+        };
+        self.error(format!("expected {expected}, found {found}"))
+    }
+
+    fn expect(&mut self, kind: Kind, expected: &str) -> Result<Token, ParseError> {
+        if *self.peek(0) != kind {
+            return self.unexpected(expected);
+        }
+        Ok(self.next())
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        match self.peek(0) {
+            Kind::Name(name) => {
+                let name = name.clone();
+                self.next();
+                Ok(name)
+            }
+            _ => self.unexpected("name"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        match self.peek(0) {
+            Kind::Str(value) => {
+                let value = value.clone();
+                self.next();
+                Ok(value)
+            }
+            _ => self.unexpected("string"),
+        }
+    }
+
+    // Consume an optional comment and the end of the line.
+    fn eol(&mut self) -> Result<(), ParseError> {
+        if let Kind::Comment(_) = self.peek(0) {
+            self.next();
+        }
+        match self.peek(0) {
+            Kind::Newline => {
+                self.next();
+                Ok(())
+            }
+            Kind::Eof => Ok(()),
+            _ => self.unexpected("end of line"),
+        }
+    }
+
+    fn raw(&self, start: usize, end: usize) -> String {
+        self.src[start..end].trim().to_string()
+    }
+
+    // An expression up to the end of the line, as raw source text.
+    fn expression(&mut self) -> Result<String, ParseError> {
+        if is_eol(self.peek(0)) {
+            return self.unexpected("expression");
+        }
+        let start = self.token().span.start;
+        let mut end = start;
+        while !is_eol(self.peek(0)) {
+            if let Kind::Text(_) = self.peek(0) {
+                return self.unexpected("expression");
+            }
+            end = self.next().span.end;
+        }
+        Ok(self.raw(start, end))
+    }
+
+    // A single value: a string, backtick, variable, function call or parenthesized expression.
+    fn value(&mut self) -> Result<String, ParseError> {
+        let start = self.token().span.start;
+        match self.peek(0) {
+            Kind::Str(_) | Kind::Backtick(_) => {
+                self.next();
+            }
+            Kind::Name(_) => {
+                self.next();
+                if *self.peek(0) == Kind::LParen {
+                    self.parenthesized()?;
+                }
+            }
+            Kind::LParen => self.parenthesized()?,
+            _ => return self.unexpected("value"),
+        }
+        let end = self.tokens[self.pos - 1].span.end;
+        Ok(self.raw(start, end))
+    }
+
+    // Skip over a balanced `(...)`.
+    fn parenthesized(&mut self) -> Result<(), ParseError> {
+        self.expect(Kind::LParen, "`(`")?;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next().kind {
+                Kind::LParen => depth += 1,
+                Kind::RParen => depth -= 1,
+                Kind::Eof => return self.error("unclosed `(`"),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn parse(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek(0).clone() {
+                Kind::Eof => break,
+                Kind::Newline => {
+                    self.next();
+                    self.comment = None;
+                }
+                Kind::Comment(comment) => {
+                    self.eol()?;
+                    // Shebang lines are not documentation.
+                    self.comment = (!comment.starts_with('!')).then_some(comment);
+                }
+                Kind::LBracket => self.attribute_list()?,
+                Kind::At => self.recipe()?,
+                Kind::Name(name) => {
+                    self.item(&name)?;
+                    self.comment = None;
+                }
+                Kind::Text(_) => return self.error("unexpected indented line"),
+                _ => return self.unexpected("recipe, assignment, or setting"),
+            }
+        }
+        if !self.attributes.is_empty() {
+            return self.error("attributes must be followed by a recipe, alias, or module");
+        }
+        Ok(())
+    }
+
+    fn item(&mut self, keyword: &str) -> Result<(), ParseError> {
+        let (next, after) = (self.peek(1), self.peek(2));
+        let named = matches!(next, Kind::Name(_));
+        match keyword {
+            "alias" if named && *after == Kind::ColonEq => self.alias(),
+            "export" if named && *after == Kind::ColonEq => {
+                self.next();
+                self.assignment(true)
+            }
+            "unexport" if named && is_eol(after) => {
+                self.next();
+                self.next();
+                self.eol()
+            }
+            "set" if named && (*after == Kind::ColonEq || is_eol(after)) => self.setting(),
+            "import" if matches!(next, Kind::Str(_) | Kind::Question) => self.import(),
+            "mod"
+                if *next == Kind::Question
+                    || named && (is_eol(after) || matches!(after, Kind::Str(_))) =>
+            {
+                self.module()
+            }
+            _ if *next == Kind::ColonEq => self.assignment(false),
+            _ => self.recipe(),
+        }
+    }
+
+    fn attribute_list(&mut self) -> Result<(), ParseError> {
+        self.expect(Kind::LBracket, "`[`")?;
+        loop {
+            let name = self.name()?;
+            let mut attribute = Attribute {
+                name,
+                args: Vec::new(),
+                keywords: Vec::new(),
+            };
+            match self.peek(0) {
+                Kind::Colon => {
+                    self.next();
+                    attribute.args.push(self.string()?);
+                }
+                Kind::LParen => {
+                    self.next();
+                    while *self.peek(0) != Kind::RParen {
+                        if let Kind::Name(key) = self.peek(0).clone() {
+                            self.next();
+                            let value = if *self.peek(0) == Kind::Eq {
+                                self.next();
+                                Some(self.string()?)
+                            } else {
+                                None
+                            };
+                            attribute.keywords.push((key, value));
+                        } else {
+                            attribute.args.push(self.string()?);
+                        }
+                        if *self.peek(0) != Kind::Comma {
+                            break;
+                        }
+                        self.next();
+                    }
+                    self.expect(Kind::RParen, "`)`")?;
+                }
+                _ => {}
+            }
+            self.attributes.push(attribute);
+            if *self.peek(0) != Kind::Comma {
+                break;
+            }
+            self.next();
+        }
+        self.expect(Kind::RBracket, "`]`")?;
+        self.eol()
+    }
+
+    fn alias(&mut self) -> Result<(), ParseError> {
+        self.next();
+        let alias = self.name()?;
+        self.expect(Kind::ColonEq, "`:=`")?;
+        let rule = self.name()?;
+        self.eol()?;
+        self.justfile.aliases.push(Alias {
+            alias,
+            rule,
+            attributes: std::mem::take(&mut self.attributes),
+        });
+        Ok(())
+    }
+
+    fn assignment(&mut self, export: bool) -> Result<(), ParseError> {
+        let name = self.name()?;
+        self.expect(Kind::ColonEq, "`:=`")?;
+        let value = self.expression()?;
+        self.eol()?;
+        self.attributes.clear();
+        self.justfile.assignments.push(Assignment {
+            name,
+            value,
+            export,
+        });
+        Ok(())
+    }
+
+    fn setting(&mut self) -> Result<(), ParseError> {
+        self.next();
+        let name = self.name()?;
+        let value = if *self.peek(0) == Kind::ColonEq {
+            self.next();
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.eol()?;
+        self.justfile.settings.push(Setting { name, value });
+        Ok(())
+    }
+
+    fn import(&mut self) -> Result<(), ParseError> {
+        self.next();
+        let optional = *self.peek(0) == Kind::Question;
+        if optional {
+            self.next();
+        }
+        let path = self.string()?;
+        self.eol()?;
+        self.justfile.imports.push(Import { path, optional });
+        Ok(())
+    }
+
+    fn module(&mut self) -> Result<(), ParseError> {
+        self.next();
+        let optional = *self.peek(0) == Kind::Question;
+        if optional {
+            self.next();
+        }
+        let name = self.name()?;
+        let path = match self.peek(0) {
+            Kind::Str(_) => Some(self.string()?),
+            _ => None,
+        };
+        self.eol()?;
+        self.justfile.modules.push(Module {
+            name,
+            path,
+            optional,
+            attributes: std::mem::take(&mut self.attributes),
+            justfile: Justfile::default(),
+        });
+        Ok(())
+    }
+
+    fn recipe(&mut self) -> Result<(), ParseError> {
+        let line = self.token().line;
+        let quiet = *self.peek(0) == Kind::At;
+        if quiet {
+            self.next();
+        }
+        let name = self.name()?;
+
+        let mut params: Vec<Parameter> = Vec::new();
+        while *self.peek(0) != Kind::Colon {
+            let variadic = match self.peek(0) {
+                Kind::Plus => Some(Variadic::Plus),
+                Kind::Star => Some(Variadic::Star),
+                _ => None,
+            };
+            if variadic.is_some() {
+                self.next();
+            }
+            let export = *self.peek(0) == Kind::Dollar;
+            if export {
+                self.next();
+            }
+            if !matches!(self.peek(0), Kind::Name(_)) {
+                return self.unexpected("parameter or `:`");
+            }
+            let name = self.name()?;
+            let default = if *self.peek(0) == Kind::Eq {
+                self.next();
+                Some(self.value()?)
+            } else {
+                None
+            };
+            if let Some(last) = params.last() {
+                if last.variadic.is_some() {
+                    return self.error(format!(
+                        "parameter `{name}` follows variadic parameter `{}`",
+                        last.name
+                    ));
+                }
+                if default.is_none() && variadic.is_none() && last.default.is_some() {
+                    return self.error(format!(
+                        "parameter `{name}` without default follows parameter with default"
+                    ));
+                }
+            }
+            params.push(Parameter {
+                name,
+                default,
+                variadic,
+                export,
+            });
+        }
+        self.expect(Kind::Colon, "`:`")?;
+
+        let mut dependencies = Vec::new();
+        let mut after = false;
+        while !is_eol(self.peek(0)) {
+            match self.peek(0) {
+                Kind::AmpAmp if !after => {
+                    self.next();
+                    after = true;
+                }
+                Kind::Name(_) => dependencies.push(Dependency {
+                    name: self.name()?,
+                    args: Vec::new(),
+                    after,
+                }),
+                Kind::LParen => {
+                    self.next();
+                    let name = self.name()?;
+                    let mut args = Vec::new();
+                    while *self.peek(0) != Kind::RParen {
+                        args.push(self.value()?);
+                    }
+                    self.next();
+                    dependencies.push(Dependency { name, args, after });
+                }
+                _ => return self.unexpected("dependency"),
+            }
+        }
+        self.eol()?;
+
+        let mut body = Vec::new();
+        loop {
+            match self.peek(0) {
+                Kind::Text(text) => {
+                    body.push(text.clone());
+                    self.next();
+                    if *self.peek(0) == Kind::Newline {
+                        self.next();
+                    }
+                }
+                Kind::Newline => {
+                    // Blank lines only belong to the body when more body lines follow.
+                    let mut blank = 0;
+                    while *self.peek(blank) == Kind::Newline {
+                        blank += 1;
+                    }
+                    if !matches!(self.peek(blank), Kind::Text(_)) {
+                        break;
+                    }
+                    for _ in 0..blank {
+                        body.push(String::new());
+                        self.next();
+                    }
+                }
+                _ => break,
+            }
+        }
+        let indent = body.first().map_or(0, |l| l.len() - l.trim_start().len());
+        for l in &mut body {
+            *l = l.get(indent..).unwrap_or(l.trim_start()).to_string();
+        }
+
+        // A `[doc]` attribute takes precedence over the comment above the rule.
+        let doc = match self.attributes.iter().find(|a| a.name == "doc") {
+            Some(attribute) => attribute.args.first().cloned(),
+            None => self.comment.take(),
+        };
+
+        self.justfile.rules.push(Rule {
+            name,
+            doc,
+            params,
+            dependencies,
+            attributes: std::mem::take(&mut self.attributes),
+            body,
+            quiet,
+            source: None,
+            line: Some(line),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule<'j>(justfile: &'j Justfile, name: &str) -> &'j Rule {
+        justfile.rules.iter().find(|r| r.name == name).unwrap()
+    }
+
+    #[test]
+    fn assignments() {
+        let justfile = parse(
+            r#"version := "1.0"
+url := "http://localhost:8080"
+export PATH := "/bin"
+smile := "\u{1F600}"
+stable := if version !~ 'beta' { "yes" } else { "no" }
+build:
+  echo {{version}}
+"#,
+        )
+        .unwrap();
+        let assignments: Vec<_> = justfile
+            .assignments
+            .iter()
+            .map(|a| (a.name.as_str(), a.value.as_str(), a.export))
+            .collect();
+        assert_eq!(
+            assignments,
+            [
+                ("version", r#""1.0""#, false),
+                ("url", r#""http://localhost:8080""#, false),
+                ("PATH", r#""/bin""#, true),
+                ("smile", r#""\u{1F600}""#, false),
+                (
+                    "stable",
+                    r#"if version !~ 'beta' { "yes" } else { "no" }"#,
+                    false
+                ),
+            ]
+        );
+        for value in [r#""\u{110000}""#, r#""\u{}""#, r#""\u1F600""#] {
+            assert!(parse(&format!("x := {value}\n")).is_err(), "{value}");
+        }
+        // None of them is mistaken for a rule.
+        let rules: Vec<_> = justfile.rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(rules, ["build"]);
+    }
+
+    #[test]
+    fn settings_and_imports() {
+        let justfile = parse(
+            r#"set shell := ["bash", "-c"]
+set dotenv-load
+import 'common.just'
+import? "local.just"
+"#,
+        )
+        .unwrap();
+        let settings: Vec<_> = justfile
+            .settings
+            .iter()
+            .map(|s| (s.name.as_str(), s.value.as_deref()))
+            .collect();
+        assert_eq!(
+            settings,
+            [("shell", Some(r#"["bash", "-c"]"#)), ("dotenv-load", None)]
+        );
+        let imports: Vec<_> = justfile
+            .imports
+            .iter()
+            .map(|i| (i.path.as_str(), i.optional))
+            .collect();
+        assert_eq!(imports, [("common.just", false), ("local.just", true)]);
+        assert!(justfile.rules.is_empty());
+    }
+
+    #[test]
+    fn attributes() {
+        let justfile = parse(
+            r#"[private]
+[group: "ci \u{1F680}"]
+[arg("env", pattern="dev|prod")]
+[arg("region", long, short="r")]
+deploy env region:
+  echo
+"#,
+        )
+        .unwrap();
+        let attributes = &rule(&justfile, "deploy").attributes;
+        let attributes: Vec<_> = attributes
+            .iter()
+            .map(|a| (a.name.as_str(), a.args.clone(), a.keywords.clone()))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                ("private", vec![], vec![]),
+                ("group", vec!["ci 🚀".to_string()], vec![]),
+                (
+                    "arg",
+                    vec!["env".to_string()],
+                    vec![("pattern".to_string(), Some("dev|prod".to_string()))]
+                ),
+                (
+                    "arg",
+                    vec!["region".to_string()],
+                    vec![
+                        ("long".to_string(), None),
+                        ("short".to_string(), Some("r".to_string()))
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parameters() {
+        let justfile = parse(
+            r#"build $target profile="release" +files:
+  echo
+test *flags:
+  echo
+"#,
+        )
+        .unwrap();
+        let params: Vec<_> = rule(&justfile, "build")
+            .params
+            .iter()
+            .map(|p| (p.name.as_str(), p.default.as_deref(), p.variadic, p.export))
+            .collect();
+        assert_eq!(
+            params,
+            [
+                ("target", None, None, true),
+                ("profile", Some(r#""release""#), None, false),
+                ("files", None, Some(Variadic::Plus), false),
+            ]
+        );
+        let flags = &rule(&justfile, "test").params[0];
+        assert_eq!(flags.variadic, Some(Variadic::Star));
+        assert!(!flags.required());
+        assert!(parse("build +files target:\n").is_err());
+    }
+
+    #[test]
+    fn dependencies() {
+        let justfile = parse(
+            r#"release: build && (greet "done") clean
+  git tag
+"#,
+        )
+        .unwrap();
+        let dependencies: Vec<_> = rule(&justfile, "release")
+            .dependencies
+            .iter()
+            .map(|d| (d.name.as_str(), d.args.clone(), d.after))
+            .collect();
+        assert_eq!(
+            dependencies,
+            [
+                ("build", vec![], false),
+                ("greet", vec![r#""done""#.to_string()], true),
+                ("clean", vec![], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn body() {
+        let justfile =
+            parse("build:\n  echo a\n\n    echo b\n  echo c\n\ntest:\n  echo\n").unwrap();
+        let build = rule(&justfile, "build");
+        // Blank lines inside the body are kept, trailing ones are not.
+        assert_eq!(build.body, ["echo a", "", "  echo b", "echo c"]);
+        assert_eq!(build.line, Some(1));
+        assert_eq!(rule(&justfile, "test").line, Some(7));
+    }
+
+    #[test]
+    fn doc_comments() {
+        let justfile = parse(
+            r#"# Build everything
+build:
+  echo
+
+# A section header
+
+test:
+  echo
+
+# Old comment
+[doc("Deploy it")]
+deploy:
+  echo
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rule(&justfile, "build").doc.as_deref(),
+            Some("Build everything")
+        );
+        // A blank line separates the comment from the rule.
+        assert_eq!(rule(&justfile, "test").doc, None);
+        assert_eq!(rule(&justfile, "deploy").doc.as_deref(), Some("Deploy it"));
+    }
+}
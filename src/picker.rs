@@ -0,0 +1,152 @@
+// A full-screen picker, like `just --choose`: the matching rules on the left,
+// and a preview of the selected rule on the right.
+use std::io::Write;
+
+use colored::Colorize;
+use termion::cursor::Goto;
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::IntoAlternateScreen;
+
+use crate::justfile::{Justfile, Match};
+use crate::show::{self, fit};
+use crate::usage::Usage;
+
+// Returns the path of the chosen rule, or None when cancelled.
+pub fn pick(justfile: &Justfile, usage: &Usage, show_private: bool, query: &str) -> Option<String> {
+    let screen = std::io::stdout().into_raw_mode().ok()?;
+    let mut screen = screen.into_alternate_screen().ok()?;
+    let chosen = run(&mut screen, justfile, usage, show_private, query);
+    // Leaving the alternate screen is only written on drop, and stdout is line buffered.
+    drop(screen);
+    let _ = std::io::stdout().flush();
+    chosen
+}
+
+fn run(
+    screen: &mut impl Write,
+    justfile: &Justfile,
+    usage: &Usage,
+    show_private: bool,
+    query: &str,
+) -> Option<String> {
+    let mut keys = std::io::stdin().keys();
+    let mut query = query.trim().to_string();
+    let mut selected = 0;
+    let mut scroll = 0;
+    loop {
+        let matches = justfile.matches(&query, usage, show_private);
+        let (cols, rows) = termion::terminal_size().unwrap_or((80, 24));
+        // The first row holds the query.
+        let height = (rows as usize).saturating_sub(1).max(1);
+        selected = selected.min(matches.len().saturating_sub(1));
+        // Scroll just enough to keep the selection visible.
+        scroll = scroll.clamp(selected.saturating_sub(height - 1), selected);
+        let view = View {
+            query: &query,
+            matches: &matches,
+            selected,
+            scroll,
+        };
+        view.draw(screen, cols as usize, height).ok()?;
+
+        match keys.next()?.ok()? {
+            Key::Char('\n') => return matches.get(selected).map(|m| m.path.clone()),
+            Key::Esc | Key::Ctrl('c') | Key::Ctrl('d') | Key::Ctrl('g') => return None,
+            Key::Up | Key::Ctrl('p') | Key::BackTab => selected = selected.saturating_sub(1),
+            Key::Down | Key::Ctrl('n') | Key::Char('\t') => selected += 1,
+            Key::PageUp => selected = selected.saturating_sub(height),
+            Key::PageDown => selected += height,
+            Key::Home => selected = 0,
+            Key::End => selected = matches.len(),
+            Key::Backspace => {
+                query.pop();
+                selected = 0;
+            }
+            Key::Ctrl('u') => {
+                query.clear();
+                selected = 0;
+            }
+            Key::Char(c) if !c.is_control() => {
+                query.push(c);
+                selected = 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+struct View<'a> {
+    query: &'a str,
+    matches: &'a [Match<'a>],
+    selected: usize,
+    scroll: usize,
+}
+
+impl View<'_> {
+    fn draw(&self, out: &mut impl Write, cols: usize, height: usize) -> std::io::Result<()> {
+        let list_width = (cols * 2 / 5).clamp(20, 50).min(cols);
+        let preview_width = cols.saturating_sub(list_width + 2);
+        let preview = match self.matches.get(self.selected) {
+            Some(m) => show::describe(m.rule, &m.path, preview_width),
+            None => Vec::new(),
+        };
+
+        let mut s = format!("{}{}", termion::clear::All, Goto(1, 1));
+        let count = format!("  {}", self.matches.len());
+        s.push_str(&format!(
+            "{} {}{}",
+            ">".bold().red(),
+            self.query,
+            count.dimmed()
+        ));
+        for row in 0..height {
+            let y = row as u16 + 2;
+            if let Some(m) = self.matches.get(self.scroll + row) {
+                let selected = self.scroll + row == self.selected;
+                s.push_str(&format!("{}{}", Goto(1, y), item(m, selected, list_width)));
+            }
+            if preview_width > 0 {
+                s.push_str(&format!(
+                    "{}{} ",
+                    Goto(list_width as u16 + 1, y),
+                    "│".dimmed()
+                ));
+                if let Some(line) = preview.get(row) {
+                    s.push_str(line);
+                }
+            }
+        }
+        let cursor = self.query.chars().count() as u16 + 3;
+        s.push_str(&Goto(cursor, 1).to_string());
+        write!(out, "{s}")?;
+        out.flush()
+    }
+}
+
+// A rule in the list, with the matched characters underlined.
+fn item(m: &Match, selected: bool, width: usize) -> String {
+    let mut s = match selected {
+        true => format!("{} ", ">".bold().red()),
+        false => "  ".to_string(),
+    };
+    let target = match m.alias {
+        Some(_) => format!(" → {}", m.path),
+        None => String::new(),
+    };
+    let text = fit(&format!("{}{target}", m.name), width.saturating_sub(3));
+    for (i, c) in text.chars().enumerate() {
+        let c = c.to_string();
+        let c = match (i < m.name.chars().count(), m.indices.contains(&i)) {
+            (true, true) => c.bold().underline(),
+            (true, false) if selected => c.bold(),
+            _ => c.normal(),
+        };
+        s.push_str(&match selected {
+            true => c.green().to_string(),
+            false => c.to_string(),
+        });
+    }
+    s
+}
@@ -0,0 +1,117 @@
+// Running `just` as a foreground job: in its own process group that owns the terminal,
+// so that Ctrl-C and Ctrl-Z go to the recipe and not to the shell.
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Command, ExitStatus};
+
+use libc::{STDIN_FILENO, c_int};
+
+const SIGNALS: [(c_int, &str); 15] = [
+    (libc::SIGHUP, "SIGHUP"),
+    (libc::SIGINT, "SIGINT"),
+    (libc::SIGQUIT, "SIGQUIT"),
+    (libc::SIGILL, "SIGILL"),
+    (libc::SIGTRAP, "SIGTRAP"),
+    (libc::SIGABRT, "SIGABRT"),
+    (libc::SIGBUS, "SIGBUS"),
+    (libc::SIGFPE, "SIGFPE"),
+    (libc::SIGKILL, "SIGKILL"),
+    (libc::SIGUSR1, "SIGUSR1"),
+    (libc::SIGSEGV, "SIGSEGV"),
+    (libc::SIGUSR2, "SIGUSR2"),
+    (libc::SIGPIPE, "SIGPIPE"),
+    (libc::SIGALRM, "SIGALRM"),
+    (libc::SIGTERM, "SIGTERM"),
+];
+
+pub fn signal_name(signal: c_int) -> String {
+    match SIGNALS.iter().find(|(s, _)| *s == signal) {
+        Some((_, name)) => name.to_string(),
+        None => format!("signal {signal}"),
+    }
+}
+
+// The signal that probably killed a recipe, given just's exit code. Just does not die
+// from the signal that kills a recipe, but exits with 128 plus its number, like shells
+// do; a recipe may also exit with such a code itself.
+pub fn likely_signal(code: i32) -> Option<&'static str> {
+    let (_, name) = SIGNALS
+        .iter()
+        .find(|(s, _)| code > 128 && *s == code - 128)?;
+    Some(name)
+}
+
+// The exit code, or the name of the signal that killed the process.
+pub fn status_label(status: &ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => code.to_string(),
+        (None, Some(signal)) => signal_name(signal),
+        (None, None) => "?".to_string(),
+    }
+}
+
+fn is_terminal() -> bool {
+    unsafe { libc::isatty(STDIN_FILENO) == 1 }
+}
+
+// Give the terminal to the process group `pgid`.
+fn foreground(pgid: libc::pid_t) {
+    unsafe {
+        libc::tcsetpgrp(STDIN_FILENO, pgid);
+    }
+}
+
+pub fn init() {
+    // Taking the terminal back from a finished job sends SIGTTOU to the shell,
+    // since it is not in the foreground at that point.
+    unsafe {
+        libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+    }
+}
+
+pub fn run(command: &mut Command) -> std::io::Result<ExitStatus> {
+    let terminal = is_terminal();
+    command.process_group(0);
+    if terminal {
+        // Also take the terminal in the child, so that it owns it before running anything.
+        unsafe {
+            command.pre_exec(|| {
+                libc::tcsetpgrp(STDIN_FILENO, libc::getpid());
+                libc::signal(libc::SIGTTOU, libc::SIG_DFL);
+                Ok(())
+            });
+        }
+    }
+    let child = command.spawn()?;
+    let pid = child.id() as libc::pid_t;
+    if terminal {
+        foreground(pid);
+    }
+
+    let status = loop {
+        let mut status = 0;
+        if unsafe { libc::waitpid(pid, &mut status, libc::WUNTRACED) } < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            break Err(err);
+        }
+        if !libc::WIFSTOPPED(status) {
+            break Ok(ExitStatus::from_raw(status));
+        }
+        // Ctrl-Z stopped the job: suspend the shell as well, like before the job had
+        // its own process group, and continue the job when the shell is resumed.
+        unsafe {
+            foreground(libc::getpgrp());
+            libc::kill(0, libc::SIGTSTP);
+            if terminal {
+                foreground(pid);
+            }
+            libc::kill(-pid, libc::SIGCONT);
+        }
+    };
+    if terminal {
+        foreground(unsafe { libc::getpgrp() });
+    }
+    status
+}
@@ -0,0 +1,91 @@
+// Prompting for the required arguments of a rule that were not given on the command line.
+use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+
+use colored::Colorize;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+
+use crate::MyHinter;
+use crate::args::ArgHistory;
+use crate::justfile::Match;
+
+// State of the helper while prompting for a single argument.
+pub struct ArgPrompt {
+    // Values previously used for this parameter, most recent first,
+    // followed by its other choices, if any.
+    pub values: Vec<String>,
+}
+
+impl ArgPrompt {
+    pub fn candidates(&self, prefix: &str) -> Vec<&str> {
+        self.values
+            .iter()
+            .map(String::as_str)
+            .filter(|v| v.starts_with(prefix))
+            .collect()
+    }
+}
+
+impl MyHinter<'_> {
+    pub fn set_arg_prompt(&mut self, arg_prompt: Option<ArgPrompt>) {
+        self.in_prompt
+            .store(arg_prompt.is_some(), Ordering::Relaxed);
+        self.arg_prompt = arg_prompt;
+    }
+}
+
+// Ask for each missing argument in turn, up to the last required parameter.
+// Returns false when the user cancels with Ctrl-C or Ctrl-D.
+pub fn missing_args(
+    rl: &mut rustyline::Editor<MyHinter, DefaultHistory>,
+    arg_history: &Mutex<ArgHistory>,
+    m: &Match,
+    args: &mut Vec<String>,
+) -> bool {
+    let (min, _) = m.rule.arity();
+    while args.len() < min {
+        let index = args.len();
+        let param = m.rule.param_at(index).unwrap();
+        let mut values = previous_values(&arg_history.lock().unwrap(), &m.path, index);
+        for choice in m.rule.choices(&param.name).unwrap_or_default() {
+            if !values.contains(&choice) {
+                values.push(choice);
+            }
+        }
+        rl.helper_mut()
+            .unwrap()
+            .set_arg_prompt(Some(ArgPrompt { values }));
+        let prompt = format!("{} {}: ", m.path.bold(), param.usage());
+        let initial = param.default_value().unwrap_or_default();
+        let line = rl.readline_with_initial(&prompt, (&initial, ""));
+        rl.helper_mut().unwrap().set_arg_prompt(None);
+        match line {
+            Ok(line) if param.variadic.is_some() => {
+                args.extend(line.split_whitespace().map(str::to_string))
+            }
+            // Ask again rather than passing an empty argument.
+            Ok(line) if line.trim().is_empty() && param.required() => {}
+            Ok(line) => args.push(line.trim().to_string()),
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => return false,
+            Err(err) => {
+                eprintln!("Error: {err:?}");
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// Arguments at `index` in earlier runs of the rule, most recent first.
+fn previous_values(arg_history: &ArgHistory, path: &str, index: usize) -> Vec<String> {
+    let mut values = Vec::new();
+    for args in arg_history.get(path).iter().rev() {
+        if let Some(value) = args.get(index)
+            && !values.contains(value)
+        {
+            values.push(value.clone());
+        }
+    }
+    values
+}
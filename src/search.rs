@@ -0,0 +1,88 @@
+// Find the justfile the same way `just` does: walk up from the current directory
+// and accept any case variant of `justfile` or `.justfile`.
+use std::path::{Path, PathBuf};
+
+const JUSTFILE_NAMES: [&str; 2] = ["justfile", ".justfile"];
+
+#[derive(Clone)]
+pub struct Search {
+    pub justfile: PathBuf,
+    // Recipes run in the directory containing the justfile.
+    pub working_directory: PathBuf,
+}
+
+// All files in `dir` that look like a justfile.
+pub fn candidates(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut candidates = Vec::new();
+    let entries =
+        std::fs::read_dir(dir).map_err(|err| format!("could not read {}: {err}", dir.display()))?;
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if JUSTFILE_NAMES.contains(&name.to_lowercase().as_str()) && entry.path().is_file() {
+            candidates.push(entry.path());
+        }
+    }
+    candidates.sort();
+    Ok(candidates)
+}
+
+pub fn find(start: &Path) -> Result<Search, String> {
+    for dir in start.ancestors() {
+        let mut candidates = candidates(dir)?;
+        match candidates.len() {
+            0 => continue,
+            1 => {
+                let justfile = candidates.pop().unwrap();
+                return Ok(Search {
+                    justfile,
+                    working_directory: dir.to_path_buf(),
+                });
+            }
+            _ => {
+                let names: Vec<_> = candidates
+                    .iter()
+                    .map(|c| c.file_name().unwrap().to_string_lossy())
+                    .collect();
+                return Err(format!(
+                    "multiple candidate justfiles found in {}: {}",
+                    dir.display(),
+                    names.join(" and ")
+                ));
+            }
+        }
+    }
+    Err(format!(
+        "no justfile found in {} or any parent directory",
+        start.display()
+    ))
+}
+
+// Use an explicitly given justfile, as with `just --justfile <path>`.
+// Without a working directory, recipes run next to the justfile.
+pub fn explicit(
+    cwd: &Path,
+    justfile: &Path,
+    working_directory: Option<&Path>,
+) -> Result<Search, String> {
+    let justfile = cwd.join(justfile);
+    if !justfile.is_file() {
+        return Err(format!("{} is not a file", justfile.display()));
+    }
+    let working_directory = match working_directory {
+        Some(dir) => cwd.join(dir),
+        None => justfile.parent().unwrap().to_path_buf(),
+    };
+    if !working_directory.is_dir() {
+        return Err(format!(
+            "{} is not a directory",
+            working_directory.display()
+        ));
+    }
+    Ok(Search {
+        justfile,
+        working_directory,
+    })
+}
@@ -0,0 +1,153 @@
+// Describing a rule: what `:show` prints, and the preview of the picker.
+use colored::Colorize;
+
+use crate::justfile::{Justfile, Rule};
+use crate::usage::Usage;
+
+// `:show <pattern>`: print the rule that the pattern runs.
+pub fn print(justfile: &Justfile, usage: &Usage, show_private: bool, pattern: &str) {
+    let Some(m) = justfile.best_match(Some(pattern), usage, show_private) else {
+        crate::no_match(justfile, pattern, show_private);
+        return;
+    };
+    if m.alias.is_some() {
+        println!(
+            "{}",
+            format!("{} is an alias for {}", m.name, m.path).dimmed()
+        );
+    }
+    for line in describe(m.rule, &m.path, usize::MAX) {
+        println!("{line}");
+    }
+}
+
+// The signature, documentation, attributes, dependencies, source and body of a rule,
+// one line per entry, each cut to `width` characters.
+pub fn describe(rule: &Rule, path: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![fit(&rule.signature(path), width).bold().green().to_string()];
+    if let Some(doc) = &rule.doc {
+        for line in doc.lines() {
+            lines.push(fit(line, width).italic().to_string());
+        }
+    }
+
+    let mut details = Vec::new();
+    if !rule.attributes.is_empty() {
+        let attributes: Vec<_> = rule.attributes.iter().map(|a| a.to_string()).collect();
+        details.push(("attributes", attributes.join(" ")));
+    }
+    let dependencies = |after: bool| {
+        let dependencies: Vec<_> = rule
+            .dependencies
+            .iter()
+            .filter(|d| d.after == after)
+            .map(|d| d.to_string())
+            .collect();
+        dependencies.join(" ")
+    };
+    let (before, after) = (dependencies(false), dependencies(true));
+    if !before.is_empty() {
+        details.push(("depends on", before));
+    }
+    if !after.is_empty() {
+        details.push(("followed by", after));
+    }
+    if let (Some(source), Some(line)) = (&rule.source, rule.line) {
+        details.push(("defined at", format!("{}:{line}", source.display())));
+    }
+    if !details.is_empty() {
+        lines.push(String::new());
+    }
+    for (label, value) in details {
+        let label = format!("{label}: ");
+        let value = fit(&value, width.saturating_sub(label.len()));
+        lines.push(format!("{}{value}", label.dimmed()));
+    }
+
+    if !rule.body.is_empty() {
+        lines.push(String::new());
+    }
+    let shebang = rule.body.first().is_some_and(|l| l.starts_with("#!"));
+    for (i, line) in rule.body.iter().enumerate() {
+        let line = fit(&line.replace('\t', "    "), width);
+        lines.push(match (i, shebang) {
+            (0, true) => line.magenta().to_string(),
+            _ => highlight(&line, !shebang),
+        });
+    }
+    lines
+}
+
+// Highlight a line of a recipe body: `{{interpolations}}`, quoted strings and comments,
+// and for linewise recipes the command and the `@`/`-` prefixes.
+fn highlight(line: &str, linewise: bool) -> String {
+    let indent = line.len() - line.trim_start().len();
+    let (indent, mut rest) = line.split_at(indent);
+    let mut s = indent.to_string();
+    if rest.starts_with('#') {
+        return format!("{s}{}", rest.dimmed());
+    }
+    if linewise {
+        let prefix = rest.len() - rest.trim_start_matches(['@', '-']).len();
+        s.push_str(&rest[..prefix].bold().to_string());
+        rest = &rest[prefix..];
+    }
+
+    let mut command = linewise;
+    let mut quote = None;
+    let mut word = String::new();
+    let flush = |word: &mut String, s: &mut String, command: &mut bool| {
+        if *command && !word.is_empty() {
+            s.push_str(&word.bold().to_string());
+            *command = false;
+        } else {
+            s.push_str(word);
+        }
+        word.clear();
+    };
+    let mut chars = rest.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if rest[i..].starts_with("{{")
+            && let Some(end) = rest[i..].find("}}")
+        {
+            flush(&mut word, &mut s, &mut command);
+            s.push_str(&rest[i..i + end + 2].yellow().to_string());
+            while chars.next_if(|(j, _)| *j < i + end + 2).is_some() {}
+            continue;
+        }
+        match (quote, c) {
+            (None, '"' | '\'') => {
+                flush(&mut word, &mut s, &mut command);
+                quote = Some(c);
+                s.push_str(&c.to_string().green().to_string());
+            }
+            (Some(q), c) if c == q => {
+                quote = None;
+                s.push_str(&c.to_string().green().to_string());
+            }
+            (Some(_), c) => s.push_str(&c.to_string().green().to_string()),
+            (None, '#') if word.is_empty() => {
+                flush(&mut word, &mut s, &mut command);
+                s.push_str(&rest[i..].dimmed().to_string());
+                break;
+            }
+            (None, c) if c.is_whitespace() => {
+                flush(&mut word, &mut s, &mut command);
+                s.push(c);
+            }
+            (None, c) => word.push(c),
+        }
+    }
+    flush(&mut word, &mut s, &mut command);
+    s
+}
+
+// Cut `s` to at most `width` characters, ending in `…` when cut.
+pub fn fit(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        return s.to_string();
+    }
+    let mut s: String = s.chars().take(width.saturating_sub(1)).collect();
+    s.push('…');
+    s
+}
@@ -0,0 +1,23 @@
+// Per-project state, stored under $XDG_STATE_HOME/just-shell/.
+use std::path::{Path, PathBuf};
+
+fn state_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(base.join("just-shell"))
+}
+
+// The state file with the given extension for a justfile.
+// Files are keyed by the canonical path of the justfile with `/` replaced by `%`,
+// e.g. `%home%user%project%justfile.history`.
+pub fn project_file(justfile: &Path, extension: &str) -> Option<PathBuf> {
+    let dir = state_dir()?;
+    std::fs::create_dir_all(&dir).ok()?;
+    let justfile = justfile.canonicalize().ok()?;
+    let key = justfile
+        .to_string_lossy()
+        .replace(std::path::MAIN_SEPARATOR, "%");
+    Some(dir.join(format!("{key}.{extension}")))
+}
@@ -0,0 +1,104 @@
+// How long each run took, persisted per project, for `:times` and the prompt.
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use colored::Colorize;
+
+use crate::state;
+
+// Number of runs kept in the file.
+const TIMES_SIZE: usize = 500;
+
+pub struct Run {
+    // Unix timestamp in seconds.
+    pub start: u64,
+    pub duration: Duration,
+    // The exit code, or the name of the signal that killed the run.
+    pub status: String,
+    pub path: String,
+    pub args: Vec<String>,
+}
+
+impl Run {
+    pub fn success(&self) -> bool {
+        self.status == "0"
+    }
+}
+
+#[derive(Default)]
+pub struct Times {
+    // Oldest first.
+    pub runs: Vec<Run>,
+    path: Option<PathBuf>,
+}
+
+impl Times {
+    // The file has one line `<start>\t<milliseconds>\t<status>\t<rule>\t<arg1>...` per run.
+    pub fn load(justfile: &Path) -> Times {
+        let path = state::project_file(justfile, "times");
+        let mut runs = Vec::new();
+        if let Some(path) = &path
+            && let Ok(content) = std::fs::read_to_string(path)
+        {
+            for line in content.lines() {
+                let mut parts = line.split('\t');
+                let (Some(start), Some(millis), Some(status), Some(rule)) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    continue;
+                };
+                let (Ok(start), Ok(millis)) = (start.parse(), millis.parse()) else {
+                    continue;
+                };
+                runs.push(Run {
+                    start,
+                    duration: Duration::from_millis(millis),
+                    status: status.to_string(),
+                    path: rule.to_string(),
+                    args: parts.map(str::to_string).collect(),
+                });
+            }
+        }
+        Times { runs, path }
+    }
+
+    pub fn record(&mut self, run: Run) {
+        self.runs.push(run);
+        if self.runs.len() > TIMES_SIZE {
+            self.runs.remove(0);
+        }
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut content = String::new();
+        for run in &self.runs {
+            let mut fields = vec![
+                run.start.to_string(),
+                run.duration.as_millis().to_string(),
+                run.status.clone(),
+                run.path.clone(),
+            ];
+            // Arguments with tabs or newlines would break the format.
+            fields.extend(run.args.iter().map(|a| a.replace(['\t', '\n'], " ")));
+            content.push_str(&fields.join("\t"));
+            content.push('\n');
+        }
+        if let Err(err) = std::fs::write(path, content) {
+            eprintln!("{}", format!("Could not save times: {err}").dimmed());
+        }
+    }
+}
+
+// E.g. `450ms`, `12.3s` or `4m05s`.
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    match millis {
+        m if m < 1000 => format!("{m}ms"),
+        m if m < 60_000 => format!("{:.1}s", m as f64 / 1000.0),
+        m => format!("{}m{:02}s", m / 60_000, m / 1000 % 60),
+    }
+}
@@ -0,0 +1,100 @@
+// How often and how recently each rule was run, persisted per project.
+// Used to rank frequently and recently used rules higher ("frecency").
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use colored::Colorize;
+
+use crate::state;
+
+// How much frecency counts relative to the fuzzy match score.
+const FRECENCY_WEIGHT: f64 = 10.0;
+
+struct Entry {
+    count: u64,
+    // Unix timestamp in seconds.
+    last_run: u64,
+}
+
+#[derive(Default)]
+pub struct Usage {
+    entries: HashMap<String, Entry>,
+    path: Option<PathBuf>,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl Usage {
+    // The file has one line `<count> <last run> <rule>` per rule.
+    pub fn load(justfile: &Path) -> Usage {
+        let path = state::project_file(justfile, "usage");
+        let mut entries = HashMap::new();
+        if let Some(path) = &path
+            && let Ok(content) = std::fs::read_to_string(path)
+        {
+            for line in content.lines() {
+                let mut parts = line.splitn(3, ' ');
+                let (Some(count), Some(last_run), Some(rule)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    continue;
+                };
+                let (Ok(count), Ok(last_run)) = (count.parse(), last_run.parse()) else {
+                    continue;
+                };
+                entries.insert(rule.to_string(), Entry { count, last_run });
+            }
+        }
+        Usage { entries, path }
+    }
+
+    pub fn record(&mut self, rule: &str) {
+        let entry = self.entries.entry(rule.to_string()).or_insert(Entry {
+            count: 0,
+            last_run: 0,
+        });
+        entry.count += 1;
+        entry.last_run = now();
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut content = String::new();
+        for (rule, entry) in &self.entries {
+            content.push_str(&format!("{} {} {rule}\n", entry.count, entry.last_run));
+        }
+        if let Err(err) = std::fs::write(path, content) {
+            eprintln!("{}", format!("Could not save usage: {err}").dimmed());
+        }
+    }
+
+    // The run count, weighted by how recently the rule was last run.
+    pub fn frecency(&self, rule: &str) -> f64 {
+        let Some(entry) = self.entries.get(rule) else {
+            return 0.0;
+        };
+        let age = now().saturating_sub(entry.last_run);
+        let weight = match age {
+            a if a < 60 * 60 => 4.0,
+            a if a < 24 * 60 * 60 => 2.0,
+            a if a < 7 * 24 * 60 * 60 => 1.0,
+            a if a < 30 * 24 * 60 * 60 => 0.5,
+            _ => 0.25,
+        };
+        entry.count as f64 * weight
+    }
+
+    // A bonus added to the fuzzy match score of a rule.
+    // Logarithmic, so that heavy use doesn't drown out the match quality.
+    pub fn bonus(&self, rule: &str) -> i64 {
+        (FRECENCY_WEIGHT * self.frecency(rule).ln_1p()) as i64
+    }
+}
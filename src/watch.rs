@@ -0,0 +1,110 @@
+// Reload the justfile when it, or a file it imports, changes on disk.
+// A thread polls the modification times and parses the new version; the hinter and the
+// main loop pick it up from `Reloaded` the next time they look at the justfile.
+// Rustyline's external printer is not used to report the reload right away: while one
+// exists, rustyline holds back keys that arrive together until the next key is typed.
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use colored::Colorize;
+
+use crate::justfile::Justfile;
+use crate::load;
+use crate::search::Search;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub struct Pending {
+    // A newly loaded justfile, waiting to replace the current one.
+    justfile: Option<Justfile>,
+    // What changed, to be printed before the next prompt.
+    notices: Vec<String>,
+}
+
+pub type Reloaded = Arc<Mutex<Pending>>;
+
+pub fn spawn(search: Search, justfile: &Justfile, reloaded: Reloaded) {
+    let mut recipes = recipes(justfile);
+    let mut files = modified(load::sources(&search.justfile));
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let current = modified(files.iter().map(|(path, _)| path.clone()).collect());
+            if current == files {
+                continue;
+            }
+            // Read before locking: the hinter waits for the lock on every key.
+            let read = crate::read(&search);
+            // Imports may have been added or removed.
+            files = modified(load::sources(&search.justfile));
+            let mut pending = reloaded.lock().unwrap();
+            match read {
+                Ok((justfile, _)) => {
+                    let new = self::recipes(&justfile);
+                    let notice = changes(&recipes, &new);
+                    recipes = new;
+                    pending.justfile = Some(justfile);
+                    pending.notices.push(notice.dimmed().to_string());
+                }
+                Err(err) => {
+                    let notice = format!("Error: could not reload: {err}");
+                    pending.notices.push(notice.bold().red().to_string());
+                }
+            }
+        }
+    });
+}
+
+// Replace the justfile by the reloaded one, unless it is in use.
+pub fn apply(reloaded: &Reloaded, justfile: &RefCell<Justfile>) {
+    let mut pending = reloaded.lock().unwrap();
+    if pending.justfile.is_some()
+        && let Ok(mut justfile) = justfile.try_borrow_mut()
+    {
+        *justfile = pending.justfile.take().unwrap();
+    }
+}
+
+pub fn notices(reloaded: &Reloaded) -> Vec<String> {
+    std::mem::take(&mut reloaded.lock().unwrap().notices)
+}
+
+fn modified(files: Vec<PathBuf>) -> Vec<(PathBuf, Option<SystemTime>)> {
+    files
+        .into_iter()
+        .map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+fn recipes(justfile: &Justfile) -> BTreeSet<String> {
+    justfile
+        .candidates()
+        .into_iter()
+        .filter(|m| m.alias.is_none())
+        .map(|m| m.path)
+        .collect()
+}
+
+// E.g. `justfile reloaded: added deploy, test; removed build`.
+fn changes(old: &BTreeSet<String>, new: &BTreeSet<String>) -> String {
+    let added: Vec<_> = new.difference(old).map(String::as_str).collect();
+    let removed: Vec<_> = old.difference(new).map(String::as_str).collect();
+    let mut changes = Vec::new();
+    if !added.is_empty() {
+        changes.push(format!("added {}", added.join(", ")));
+    }
+    if !removed.is_empty() {
+        changes.push(format!("removed {}", removed.join(", ")));
+    }
+    match changes.is_empty() {
+        true => "justfile reloaded".to_string(),
+        false => format!("justfile reloaded: {}", changes.join("; ")),
+    }
+}